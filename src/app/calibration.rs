@@ -0,0 +1,118 @@
+// Captures a calibration curve by steering the needle to evenly spaced marks on the dial, one at a time.
+// The raw value the user ends up at for each mark becomes a point of the curve. Every change is shown on the device
+// right away, which holds the value until the wizard is done so telemetry doesn't move the needle away.
+
+use eframe::egui;
+
+use crate::hardware::{Calibration, CalibrationPoint, DeviceId, Interpolation};
+
+pub enum WizardAction {
+    None,
+    Hold(u16),                      // Show this raw value on the device
+    Finish(Calibration),
+    Cancel,
+}
+
+pub struct CalibrationWizard {
+    pub device: DeviceId,
+    max_value: u16,
+    current: Calibration,           // Seeds each point, so recalibrating starts from where the needle already was
+    point_count: usize,
+    interpolation: Interpolation,
+    points: Vec<CalibrationPoint>,
+    step: Option<usize>,            // None while picking how many points to capture
+}
+
+impl CalibrationWizard {
+    pub fn new(device: DeviceId, max_value: u16, current: &Calibration) -> Self {
+        Self {
+            device,
+            max_value,
+            current: current.clone(),
+            point_count: current.points.len().clamp(5, 17),
+            interpolation: current.interpolation,
+            points: Vec::new(),
+            step: None,
+        }
+    }
+
+    fn raw(&self, step: usize) -> u16 {
+        self.points[step].raw.round().clamp(0.0, u16::MAX as f32) as u16
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> WizardAction {
+        let Some(step) = self.step else {
+            ui.label("The needle is stepped through evenly spaced marks on the dial, from zero to the max value.");
+            ui.horizontal(|ui| {
+                ui.label("Marks");
+                ui.add(egui::DragValue::new(&mut self.point_count).clamp_range(2..=17));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Between marks");
+                ui.radio_value(&mut self.interpolation, Interpolation::Linear, "Straight lines");
+                ui.radio_value(&mut self.interpolation, Interpolation::Spline, "Smooth curve");
+            });
+            let mut action = WizardAction::None;
+            ui.horizontal(|ui| {
+                if ui.button("Start").clicked() {
+                    let steps = (self.point_count - 1) as f32;
+                    self.points = (0..self.point_count).map(|i| {
+                        let value = self.max_value as f32 * i as f32 / steps;
+                        CalibrationPoint { value, raw: self.current.apply(value) }
+                    }).collect();
+                    self.step = Some(0);
+                    action = WizardAction::Hold(self.raw(0));
+                }
+                if ui.button("Cancel").clicked() {
+                    action = WizardAction::Cancel;
+                }
+            });
+            return action;
+        };
+
+        let mut action = WizardAction::None;
+        let point = &mut self.points[step];
+        ui.strong(format!("Mark {} of {}", step + 1, self.point_count));
+        ui.label(format!("Move the needle until it points at {:.0} on the dial.", point.value));
+        ui.horizontal(|ui| {
+            let mut raw = point.raw.round() as i32;
+            let mut changed = false;
+            for delta in [-100, -10, -1] {
+                if ui.button(delta.to_string()).clicked() {
+                    raw += delta;
+                    changed = true;
+                }
+            }
+            // The device clamps anything past its max value
+            changed |= ui.add(egui::DragValue::new(&mut raw).clamp_range(0..=self.max_value as i32)).changed();
+            for delta in [1, 10, 100] {
+                if ui.button(format!("+{delta}")).clicked() {
+                    raw += delta;
+                    changed = true;
+                }
+            }
+            if changed {
+                point.raw = raw.clamp(0, self.max_value as i32) as f32;
+                action = WizardAction::Hold(point.raw as u16);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.add_enabled(step > 0, egui::Button::new("Back")).clicked() {
+                self.step = Some(step - 1);
+                action = WizardAction::Hold(self.raw(step - 1));
+            }
+            if step + 1 < self.point_count {
+                if ui.button("Next").clicked() {
+                    self.step = Some(step + 1);
+                    action = WizardAction::Hold(self.raw(step + 1));
+                }
+            } else if ui.button("Finish").clicked() {
+                action = WizardAction::Finish(Calibration { interpolation: self.interpolation, points: self.points.clone() });
+            }
+            if ui.button("Cancel").clicked() {
+                action = WizardAction::Cancel;
+            }
+        });
+        action
+    }
+}
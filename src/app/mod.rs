@@ -1,51 +1,431 @@
-use tokio::sync::mpsc;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use tokio::sync::{mpsc, watch};
 use eframe::egui;
 
-use crate::telemetry::Telemetry;
-use crate::hardware::{HwBoundEvent, AppBoundEvent};
+use crate::telemetry::{Telemetry, TelemetryReceiver, DashLights};
+use crate::hardware::{HwBoundEvent, AppBoundEvent, HardwareConfig, ConnectionState, DeviceDescriptor, DeviceId, DeviceKind, DeviceSettings};
+use crate::config::Config;
+use crate::backend::GamesConfig;
+
+mod calibration;
+
+use calibration::{CalibrationWizard, WizardAction};
 
-pub fn main(rx: mpsc::Receiver<Telemetry>, hw_tx: mpsc::Sender<HwBoundEvent>, hw_rx: mpsc::Receiver<AppBoundEvent>) {
+pub fn main(rx: TelemetryReceiver, hw_tx: mpsc::Sender<HwBoundEvent>, hw_rx: mpsc::Receiver<AppBoundEvent>, config: Config, config_path: PathBuf, games_config_tx: watch::Sender<GamesConfig>, hardware_config_tx: watch::Sender<HardwareConfig>) {
     let native_options = eframe::NativeOptions::default();
-    eframe::run_native("Dysoon Simhub", native_options, Box::new(|cc| Box::new( Simhub::new(cc, rx, hw_tx, hw_rx) )));
+    if let Err(e) = eframe::run_native("Dysoon Simhub", native_options, Box::new(|cc| Box::new( Simhub::new(cc, rx, hw_tx, hw_rx, config, config_path, games_config_tx, hardware_config_tx) ))) {
+        error!("Error running app: {:?}", e);
+    }
 }
 
 struct Simhub {
-    rx: mpsc::Receiver<Telemetry>,
+    rx: TelemetryReceiver,
     hw_tx: mpsc::Sender<HwBoundEvent>,
     hw_rx: mpsc::Receiver<AppBoundEvent>,
+    devices: Vec<DeviceDescriptor>,
+    stored_settings: HashMap<DeviceId, (DeviceSettings, String)>, // Last read from each device, as edited, and how the last request went
+    device_events: Vec<String>,     // Newest last
+    calibration_wizard: Option<CalibrationWizard>,
+
+    config: Config,
+    config_path: PathBuf,
+    config_status: String,
+    games_config_tx: watch::Sender<GamesConfig>,
+    hardware_config_tx: watch::Sender<HardwareConfig>,
 
     latest_telemetry: Telemetry,
 }
 
 impl Simhub {
-    fn new(_cc: &eframe::CreationContext<'_>, rx: mpsc::Receiver<Telemetry>, hw_tx: mpsc::Sender<HwBoundEvent>, hw_rx: mpsc::Receiver<AppBoundEvent>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    fn new(_cc: &eframe::CreationContext<'_>, rx: TelemetryReceiver, hw_tx: mpsc::Sender<HwBoundEvent>, hw_rx: mpsc::Receiver<AppBoundEvent>, config: Config, config_path: PathBuf, games_config_tx: watch::Sender<GamesConfig>, hardware_config_tx: watch::Sender<HardwareConfig>) -> Self {
         Self {
             rx,
             hw_tx,
             hw_rx,
+            devices: Vec::new(),
+            stored_settings: HashMap::new(),
+            device_events: Vec::new(),
+            calibration_wizard: None,
+
+            config,
+            config_path,
+            config_status: String::new(),
+            games_config_tx,
+            hardware_config_tx,
 
             latest_telemetry: Telemetry::default(),
         }
     }
+
+    fn send_to_hardware(&self, event: HwBoundEvent) {
+        if let Err(e) = self.hw_tx.blocking_send(event) {
+            error!("Error sending event to hardware: {:?}", e);
+        }
+    }
+
+    /// Every device seen since startup with its live status, and its settings
+    fn device_panel(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Refresh").clicked() {
+            self.send_to_hardware(HwBoundEvent::RequestDeviceList);
+        }
+        if self.devices.is_empty() {
+            ui.label("No devices found yet");
+        }
+
+        let mut changed = false;
+        egui::Grid::new("devices").striped(true).show(ui, |ui| {
+            for header in ["Name", "ID", "Type", "Unit", "Max value", "Firmware", "Status", "Last error", "Enabled", ""] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for device in &self.devices {
+                // Devices nobody changed anything for keep using the defaults, so they're only added to the config on an edit
+                let settings = self.config.hardware.device(&device.id);
+                let mut name = settings.map(|settings| settings.name.clone()).unwrap_or_default();
+                let mut enabled = settings.is_none_or(|settings| settings.enabled);
+                let calibrated = settings.is_some_and(|settings| settings.calibration.is_calibrated());
+                if ui.add(egui::TextEdit::singleline(&mut name).desired_width(110.0).hint_text("unnamed")).changed() {
+                    self.config.hardware.device_mut(&device.id).name = name;
+                    changed = true;
+                }
+                ui.label(device.id.to_string());
+                ui.label(match device.kind {
+                    DeviceKind::RpmGauge => "RPM gauge",
+                    DeviceKind::Unknown => "-",
+                });
+                ui.label(device.unit_type.to_string());
+                ui.label(device.max_value.to_string());
+                ui.label(&device.firmware_version);
+                let (color, state) = match device.state {
+                    ConnectionState::Connected => (egui::Color32::from_rgb(96,192,64), "connected"),
+                    ConnectionState::Disconnected => (egui::Color32::from_rgb(128,128,128), "disconnected"),
+                    ConnectionState::HandshakeFailed => (egui::Color32::from_rgb(192,64,96), "not responding"),
+                };
+                ui.colored_label(color, state);
+                ui.label(device.last_error.as_deref().unwrap_or("-"));
+                if ui.checkbox(&mut enabled, "").changed() {
+                    self.config.hardware.device_mut(&device.id).enabled = enabled;
+                    changed = true;
+                }
+                let connected = device.state == ConnectionState::Connected;
+                let can_calibrate = connected && device.max_value > 0 && self.calibration_wizard.is_none();
+                let (identify, calibrate) = ui.horizontal(|ui| (
+                    ui.add_enabled(connected, egui::Button::new("Identify")).on_hover_text("Sweeps the needle once"),
+                    ui.add_enabled(can_calibrate, egui::Button::new(if calibrated { "Recalibrate" } else { "Calibrate" }))
+                        .on_hover_text("Steps the needle through marks on the dial"),
+                )).inner;
+                if identify.clicked() {
+                    self.send_to_hardware(HwBoundEvent::Identify(device.id.clone()));
+                }
+                if calibrate.clicked() {
+                    let current = self.config.hardware.device(&device.id).map(|settings| settings.calibration.clone()).unwrap_or_default();
+                    self.calibration_wizard = Some(CalibrationWizard::new(device.id.clone(), device.max_value, &current));
+                }
+                ui.end_row();
+            }
+        });
+        if changed {
+            self.apply_hardware_config();
+        }
+
+        let (hw_tx, hardware, stored_settings) = (&self.hw_tx, &self.config.hardware, &mut self.stored_settings);
+        for device in self.devices.iter().filter(|device| device.state == ConnectionState::Connected) {
+            let name = hardware.device(&device.id).map(|settings| settings.name.as_str()).filter(|name| !name.is_empty()).unwrap_or(&device.id.0);
+            ui.collapsing(format!("Stored on {name}"), |ui| {
+                if ui.button("Read from device").clicked() {
+                    if let Err(e) = hw_tx.blocking_send(HwBoundEvent::ReadSettings(device.id.clone())) {
+                        error!("Error sending event to hardware: {:?}", e);
+                    }
+                }
+                // Writing needs the current settings, so nothing is reset by accident
+                let Some((settings, status)) = stored_settings.get_mut(&device.id) else {
+                    return;
+                };
+                egui::Grid::new(("stored_settings", &device.id)).show(ui, |ui| {
+                    ui.label("Max value");
+                    ui.add(egui::DragValue::new(&mut settings.max_value).clamp_range(1..=u16::MAX));
+                    ui.end_row();
+                    ui.label("Unit type");
+                    ui.add(egui::DragValue::new(&mut settings.unit_type));
+                    ui.end_row();
+                    ui.label("Zero offset");
+                    ui.add(egui::DragValue::new(&mut settings.zero_offset).suffix(" steps"));
+                    ui.end_row();
+                    ui.label("Needle sweep");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut settings.sweep_min).clamp_range(0..=360).suffix("°"));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(&mut settings.sweep_max).clamp_range(0..=360).suffix("°"));
+                    });
+                    ui.end_row();
+                    ui.label("Brightness");
+                    ui.add(egui::Slider::new(&mut settings.brightness, 0..=255));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Write to device").clicked() {
+                        match settings.validate() {
+                            Ok(()) => {
+                                *status = "Writing...".to_string();
+                                if let Err(e) = hw_tx.blocking_send(HwBoundEvent::WriteSettings(device.id.clone(), settings.clone())) {
+                                    error!("Error sending event to hardware: {:?}", e);
+                                }
+                            },
+                            Err(e) => *status = format!("Not written: {e}"),
+                        }
+                    }
+                    ui.label(status.as_str());
+                });
+            });
+        }
+
+        for message in &self.device_events {
+            ui.label(message);
+        }
+    }
+
+    fn calibration_window(&mut self, ctx: &egui::Context) {
+        let Some(wizard) = &mut self.calibration_wizard else {
+            return;
+        };
+        let mut action = WizardAction::None;
+        egui::Window::new(format!("Calibrate {}", wizard.device)).collapsible(false).show(ctx, |ui| action = wizard.show(ui));
+
+        let id = wizard.device.clone();
+        match action {
+            WizardAction::None => {},
+            WizardAction::Hold(raw) => self.send_to_hardware(HwBoundEvent::Hold(id, Some(raw))),
+            WizardAction::Finish(calibration) => {
+                self.config.hardware.device_mut(&id).calibration = calibration;
+                self.apply_hardware_config();
+                self.send_to_hardware(HwBoundEvent::Hold(id, None));
+                self.calibration_wizard = None;
+            },
+            WizardAction::Cancel => {
+                self.send_to_hardware(HwBoundEvent::Hold(id, None));
+                self.calibration_wizard = None;
+            },
+        }
+    }
+
+    /// Hands the edited settings to the backend and hardware, which only restart what changed
+    fn apply_config(&self) {
+        self.games_config_tx.send_if_modified(|games| {
+            let modified = *games != self.config.games;
+            *games = self.config.games.clone();
+            modified
+        });
+        self.apply_hardware_config();
+    }
+
+    /// Device settings take effect right away, without also applying game settings that may be half typed
+    fn apply_hardware_config(&self) {
+        self.hardware_config_tx.send_if_modified(|hardware| {
+            let modified = *hardware != self.config.hardware;
+            *hardware = self.config.hardware.clone();
+            modified
+        });
+    }
 }
 
 impl eframe::App for Simhub {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        match self.rx.try_recv() {
-            Ok(v) => {
-                self.latest_telemetry = v.clone();
-                self.hw_tx.blocking_send(HwBoundEvent::UpdateTelemetry(v));
-            },
-            Err(mpsc::error::TryRecvError::Empty) => {},
+        match self.rx.has_changed() {
+            Ok(true) => self.latest_telemetry = self.rx.borrow_and_update().telemetry.clone(),
+            Ok(false) => {},
             Err(e) => error!("Receiving data error: {:?}", e), // TODO: Close program with error pop-up?
         }
+        while let Ok(event) = self.hw_rx.try_recv() {
+            let message = match event {
+                AppBoundEvent::UpdateDeviceList(devices) => {
+                    self.devices = devices;
+                    continue;
+                },
+                AppBoundEvent::DeviceConnected(id) => format!("{id} connected"),
+                AppBoundEvent::DeviceDisconnected(id, error) => format!("{id} disconnected: {error}"),
+                AppBoundEvent::Settings(id, result) => {
+                    match (result, self.stored_settings.get_mut(&id)) {
+                        (Ok(settings), _) => {
+                            self.stored_settings.insert(id, (settings, "Read from the device".to_string()));
+                            continue;
+                        },
+                        (Err(e), Some((_, status))) => {
+                            *status = format!("Could not read: {e}");
+                            continue;
+                        },
+                        (Err(e), None) => format!("Could not read settings of {id}: {e}"),
+                    }
+                },
+                AppBoundEvent::SettingsWritten(id, result) => {
+                    if let Some((_, status)) = self.stored_settings.get_mut(&id) {
+                        *status = match result {
+                            Ok(()) => "Stored on the device".to_string(),
+                            Err(e) => format!("Could not write: {e}"),
+                        };
+                    }
+                    continue;
+                },
+            };
+            self.device_events.push(message);
+            if self.device_events.len() > 5 {
+                self.device_events.remove(0);
+            }
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| ui.heading(&format!("Game: {}", self.latest_telemetry.game)));
+            ui.vertical_centered(|ui| ui.heading(format!("Game: {}", self.latest_telemetry.game)));
+
+            egui::CollapsingHeader::new("Devices").default_open(true).show(ui, |ui| self.device_panel(ui));
+
+            ui.horizontal_wrapped(|ui| {
+                let telemetry = &self.latest_telemetry;
+                let pressure = |bar: f32| if telemetry.units.prefers_psi { format!("{:.1} psi", bar * 14.5038) } else { format!("{:.2} bar", bar) };
+                let gear = match telemetry.general.gear {
+                    g if g < 0 => "R".to_string(),
+                    0 => "N".to_string(),
+                    g => g.to_string(),
+                };
+                ui.label(format!("Gear: {gear}"));
+                match telemetry.general.fuel_capacity {
+                    Some(capacity) => ui.label(format!("Fuel: {:.1} / {:.0} L", telemetry.general.fuel * capacity, capacity)),
+                    None => ui.label(format!("Fuel: {:.0}%", telemetry.general.fuel * 100.0)),
+                };
+                ui.label(format!("Pedals: {:.0}% / {:.0}% / {:.0}%", telemetry.input.throttle * 100.0, telemetry.input.brake * 100.0, telemetry.input.clutch * 100.0));
+                if let Some(turbo) = telemetry.engine.turbo {
+                    ui.label(format!("Boost: {}", pressure(turbo)));
+                }
+                if let Some(engine_temp) = telemetry.engine.engine_temp {
+                    ui.label(format!("Coolant: {:.0} °C", engine_temp));
+                }
+                if let Some(oil_temp) = telemetry.engine.oil_temp {
+                    ui.label(format!("Oil: {:.0} °C", oil_temp));
+                }
+                if let Some(oil_pressure) = telemetry.engine.oil_pressure {
+                    ui.label(format!("Oil pressure: {}", pressure(oil_pressure)));
+                }
+
+                for (name, light) in DashLights::all().iter_names() {
+                    if telemetry.lights.available.contains(light) {
+                        let color = if telemetry.lights.is_on(light) { egui::Color32::from_rgb(192,64,96) } else { egui::Color32::from_rgb(64,64,64) };
+                        ui.colored_label(color, name);
+                    }
+                }
+            });
+
+            ui.collapsing("Race", |ui| {
+                let telemetry = &self.latest_telemetry;
+                let time = |t: Option<f32>| t.map(|t| format!("{}:{:06.3}", (t / 60.0) as u32, t % 60.0)).unwrap_or_else(|| "-".to_string());
+                let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+                let session = &telemetry.session;
+                ui.label(format!(
+                    "Session: {:?} ({:?}), {} laps, {} left, track {} m, air {} °C, track {} °C",
+                    session.kind,
+                    session.phase,
+                    number(session.total_laps),
+                    time(session.time_left),
+                    number(session.track_length.map(|l| l as u32)),
+                    number(session.air_temp.map(|t| t as u32)),
+                    number(session.track_temp.map(|t| t as u32)),
+                ));
+                let timing = &telemetry.timing;
+                ui.label(format!("Lap {}, position {}, {} m into the lap", number(timing.lap), number(timing.position), number(timing.lap_distance.map(|d| d.max(0.0) as u32))));
+                if let (Some(distance), Some(length)) = (timing.lap_distance, session.track_length) {
+                    ui.add(egui::ProgressBar::new((distance / length).clamp(0.0, 1.0)).show_percentage());
+                }
+                ui.label(format!("Current: {}, last: {}, best: {}", time(timing.current_lap_time), time(timing.last_lap_time), time(timing.best_lap_time)));
+                ui.label(format!("Gap ahead: {}, to leader: {}", time(timing.delta_to_car_ahead), time(timing.delta_to_leader)));
+                if let Some(delta) = timing.delta_to_best {
+                    ui.label(format!("Delta to best: {:+.3}", delta));
+                }
+                ui.label(format!("Location: {:?}", timing.location));
+                if let Some(drs) = &telemetry.drs {
+                    ui.label(format!("DRS: {}", if drs.active { "open" } else if drs.allowed { "available" } else { "closed" }));
+                }
+                if let Some(ers) = &telemetry.ers {
+                    ui.label(format!("ERS: {:.0}% stored, mode {}, {:.0}% deployed this lap", ers.store * 100.0, ers.deploy_mode, ers.deployed_this_lap * 100.0));
+                }
+            });
+
+            ui.collapsing("Settings", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Device update rate");
+                    ui.add(egui::DragValue::new(&mut self.config.hardware.update_rate_hz).clamp_range(1.0..=1000.0).suffix(" Hz"));
+                    ui.label("Look for new devices every");
+                    ui.add(egui::DragValue::new(&mut self.config.hardware.scan_interval_ms).clamp_range(100..=60000).suffix(" ms"));
+                });
+                egui::Grid::new("listeners").striped(true).show(ui, |ui| {
+                    for header in ["Telemetry", "Bind address", "Port", "Only from", "Multicast group", "Forward to", "Max forward rate"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for (name, listen) in self.config.games.listeners_mut() {
+                        ui.label(name);
+                        ui.add(egui::TextEdit::singleline(&mut listen.bind_address).desired_width(110.0));
+                        ui.add(egui::DragValue::new(&mut listen.port));
+                        ui.add(egui::TextEdit::singleline(&mut listen.source_filter).desired_width(110.0).hint_text("anyone"));
+                        ui.add(egui::TextEdit::singleline(&mut listen.multicast_group).desired_width(110.0).hint_text("none"));
+                        ui.add(egui::TextEdit::singleline(&mut listen.forward_to).desired_width(180.0).hint_text("host:port, host:port"));
+                        ui.add(egui::DragValue::new(&mut listen.forward_rate_hz).clamp_range(0.0..=1000.0).suffix(" Hz"));
+                        ui.end_row();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        self.apply_config();
+                        self.config_status = "Applied".to_string();
+                    }
+                    if ui.button("Apply and save").clicked() {
+                        self.apply_config();
+                        self.config_status = match self.config.save(&self.config_path) {
+                            Ok(()) => format!("Saved to {}", self.config_path.display()),
+                            Err(e) => format!("Could not save: {e}"),
+                        };
+                    }
+                    ui.label(&self.config_status);
+                });
+            });
 
-            if ui.button("Get device list").clicked() {
-                self.hw_tx.blocking_send(HwBoundEvent::RequestDeviceList);
+            if let Some(truck) = &self.latest_telemetry.truck {
+                ui.collapsing("Truck", |ui| {
+                    let units = &self.latest_telemetry.units;
+                    let speed = |ms: f32| if units.prefers_miles { format!("{:.0} mph", ms * 2.23694) } else { format!("{:.0} km/h", ms * 3.6) };
+                    let distance = |m: f32| if units.prefers_miles { format!("{:.1} mi", m / 1609.34) } else { format!("{:.1} km", m / 1000.0) };
+                    let air = if units.prefers_psi { format!("{:.0} psi", truck.air_pressure * 14.5038) } else { format!("{:.1} bar", truck.air_pressure) };
+                    ui.label(format!("Air pressure: {air}{}", if truck.air_pressure_warning { " (low)" } else { "" }));
+                    ui.label(format!("Cruise control: {}", truck.cruise_control.map(speed).unwrap_or_else(|| "off".to_string())));
+                    if truck.retarder_steps > 0 {
+                        ui.label(format!("Retarder: {} / {}", truck.retarder_step, truck.retarder_steps));
+                    }
+                    ui.label(format!("Engine brake: {}", if truck.engine_brake { "on" } else { "off" }));
+                    ui.label(format!(
+                        "Navigation: {} to go, limit {}",
+                        truck.navigation_distance.map(distance).unwrap_or_else(|| "-".to_string()),
+                        truck.speed_limit.map(speed).unwrap_or_else(|| "-".to_string()),
+                    ));
+                });
             }
 
+            ui.collapsing("Motion", |ui| {
+                let motion = &self.latest_telemetry.motion;
+                let [lateral, longitudinal, vertical] = motion.local_acceleration().map(|a| a / 9.81);
+                ui.label(format!("G-forces: {:.2} lateral, {:.2} longitudinal, {:.2} vertical", lateral, longitudinal, vertical));
+                ui.label(format!("Heading: {:.1}°, pitch: {:.1}°, roll: {:.1}°", motion.heading.to_degrees(), motion.pitch.to_degrees(), motion.roll.to_degrees()));
+                ui.label(format!("Angular velocity: {:.2?} rad/s", motion.angular_velocity));
+                ui.label(format!("Velocity: {:.2?} m/s", motion.velocity));
+                ui.label(format!("Position: {:.1?} m", motion.position));
+
+                for (name, wheel) in ["FL", "FR", "RL", "RR"].iter().zip(&self.latest_telemetry.wheels) {
+                    let tyre_temp = wheel.tyre_temp.map(|t| format!(", {:.0} °C", t)).unwrap_or_default();
+                    let speed = wheel.speed.map(|s| format!(" ({:.1} m/s)", s)).unwrap_or_default();
+                    ui.label(format!(
+                        "{name}: travel {:.3} m, {:.1} rad/s{speed}, load {:.0} N, slip {:.2} / {:.1}°{tyre_temp}",
+                        wheel.suspension_travel, wheel.angular_velocity, wheel.vertical_load, wheel.slip_ratio, wheel.slip_angle.to_degrees(),
+                    ));
+                }
+            });
+
             ui.columns(3, |columns| {
                 columns[0].centered_and_justified(|ui| {
                     let painter = ui.painter();
@@ -67,7 +447,7 @@ impl eframe::App for Simhub {
 
                     let start_rot = -128.0;
                     let end_rot = 128.0;
-                    let max_speed = 240.0;
+                    let max_speed = if self.latest_telemetry.units.prefers_miles { 160.0 } else { 240.0 };
                     for i in 0..13 {
                         let t = (i as f32) / 12f32;
                         let rot_deg = start_rot + (end_rot - start_rot) * t;
@@ -87,8 +467,12 @@ impl eframe::App for Simhub {
                         painter.text(p3, egui::Align2::CENTER_CENTER, format!("{}", (t * max_speed) as usize), egui::FontId::default(), egui::Color32::from_rgb(128,128,128));
                     }
 
-                    let speed_kmh = self.latest_telemetry.general.speed * 3.6;
-                    let needle_progress = speed_kmh / max_speed;
+                    let speed = if self.latest_telemetry.units.prefers_miles {
+                        self.latest_telemetry.general.speed * 2.23694
+                    } else {
+                        self.latest_telemetry.general.speed * 3.6
+                    };
+                    let needle_progress = speed / max_speed;
                     let rot_deg = start_rot + (end_rot - start_rot) * needle_progress;
                     let rot = (rot_deg - 90f32) / 180f32 * std::f32::consts::PI;
 
@@ -151,9 +535,11 @@ impl eframe::App for Simhub {
 
                     let start_rot = -128.0;
                     let end_rot = 128.0;
-                    let max_rpm = 12.0;
-                    for i in 0..13 {
-                        let t = (i as f32) / 12f32;
+                    // Scale to the car's redline, rounded up to the next thousand, if the game reports it
+                    let max_rpm = self.latest_telemetry.engine.max_rpm.map(|rpm| (rpm as f32 / 1_000f32).ceil().max(1.0)).unwrap_or(12.0);
+                    let steps = max_rpm as usize;
+                    for i in 0..=steps {
+                        let t = (i as f32) / (steps as f32);
                         let rot_deg = start_rot + (end_rot - start_rot) * t;
                         let rot = (rot_deg - 90f32) / 180f32 * std::f32::consts::PI;
                         let x = rot.cos();
@@ -168,7 +554,18 @@ impl eframe::App for Simhub {
                         let p3 = egui::Pos2::new((x * l3) + center.x, (y * l3) + center.y);
 
                         painter.line_segment([p1, p2], egui::Stroke::new(2.0, egui::Color32::from_rgb(128,128,128)));
-                        painter.text(p3, egui::Align2::CENTER_CENTER, format!("{}", (t * max_rpm) as usize), egui::FontId::default(), egui::Color32::from_rgb(128,128,128));
+                        painter.text(p3, egui::Align2::CENTER_CENTER, format!("{}", i), egui::FontId::default(), egui::Color32::from_rgb(128,128,128));
+                    }
+
+                    // Mark where the in-game shift lights start
+                    if let Some(shift_rpm) = self.latest_telemetry.engine.shift_rpm {
+                        let t = (shift_rpm as f32 / 1_000f32) / max_rpm;
+                        let rot_deg = start_rot + (end_rot - start_rot) * t;
+                        let rot = (rot_deg - 90f32) / 180f32 * std::f32::consts::PI;
+                        let (x, y) = (rot.cos(), rot.sin());
+                        let p1 = egui::Pos2::new((x * radius * 0.97) + center.x, (y * radius * 0.97) + center.y);
+                        let p2 = egui::Pos2::new((x * radius * 0.88) + center.x, (y * radius * 0.88) + center.y);
+                        painter.line_segment([p1, p2], egui::Stroke::new(4.0, egui::Color32::from_rgb(192,64,96)));
                     }
 
                     let rpm = self.latest_telemetry.engine.rpm as f32 / 1_000f32;
@@ -189,6 +586,7 @@ impl eframe::App for Simhub {
                 });
             });
         });
+        self.calibration_window(ctx);
         ctx.request_repaint();
     }
 }
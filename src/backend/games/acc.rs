@@ -0,0 +1,303 @@
+// Assetto Corsa Competizione has no telemetry stream, but its broadcasting API (meant for overlays and broadcasts)
+// covers the session, timing and car location of every car. We register as a broadcasting client and follow
+// whichever car ACC has focused, which is the player's car unless they switch cameras to another car.
+// The port and connection password are set in ACC's `Documents/Assetto Corsa Competizione/Config/broadcasting.json`.
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use tokio::net::UdpSocket;
+use tokio::time::{Duration, Instant};
+
+use crate::telemetry::*;
+use crate::backend::protocols::acc::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccConfig {
+    pub address: String,
+    pub port: u16,
+    pub display_name: String,
+    pub connection_password: String,
+    pub command_password: String,   // Only needed to control ACC, so empty by default
+    pub update_interval_ms: i32,
+}
+
+impl Default for AccConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+            display_name: "Dysoon Simhub".to_string(),
+            connection_password: "asd".to_string(), // ACC's default
+            command_password: String::new(),
+            update_interval_ms: 100,
+        }
+    }
+}
+
+pub struct BackendAcc {
+    socket: UdpSocket,
+    connection_id: i32,
+
+    focused_car_index: Option<u16>,
+    known_cars: HashSet<u16>,
+    last_entry_list_request: Instant,
+    track_meters: Option<f32>,
+
+    telemetry: Telemetry,
+}
+
+impl BackendAcc {
+    pub async fn new(config: AccConfig) -> Option<Self> {
+        let socket = match UdpSocket::bind("0.0.0.0:0").await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                return None;
+            },
+            Ok(socket) => socket,
+        };
+        if let Err(e) = socket.connect((config.address.as_str(), config.port)).await {
+            error!("Error: {:?}", e);
+            return None;
+        }
+
+        let registration = match Self::register(&socket, &config).await {
+            Ok(registration) => registration,
+            Err(e) => {
+                debug!("ACC registration failed, is a session loaded? {:?}", e);
+                return None;
+            },
+        };
+        if !registration.success {
+            error!("ACC refused the connection: {}", registration.error_message);
+            return None;
+        }
+
+        let backend = Self {
+            socket,
+            connection_id: registration.connection_id,
+
+            focused_car_index: None,
+            known_cars: HashSet::new(),
+            last_entry_list_request: Instant::now(),
+            track_meters: None,
+
+            telemetry: Telemetry {
+                game: "Assetto Corsa Competizione",
+                ..Default::default()
+            },
+        };
+        for request in [encode_request_entry_list(backend.connection_id), encode_request_track_data(backend.connection_id)] {
+            if let Err(e) = backend.socket.send(&request).await {
+                error!("Error: {:?}", e);
+                return None;
+            }
+        }
+        Some(backend)
+    }
+
+    async fn register(socket: &UdpSocket, config: &AccConfig) -> anyhow::Result<RegistrationResult> {
+        socket.send(&encode_register(&config.display_name, &config.connection_password, config.update_interval_ms, &config.command_password)).await?;
+        let mut buf = [0u8; 2048];
+        tokio::time::timeout(Duration::from_millis(500), async {
+            loop {
+                let n = socket.recv(&mut buf).await?;
+                // Updates for an earlier connection may still be arriving, skip anything that isn't our result
+                if let Ok(AccMessage::RegistrationResult(result)) = AccMessage::parse(&buf[..n]) {
+                    return Ok(result);
+                }
+            }
+        }).await?
+    }
+
+    /// Asks ACC for the entry list again, at most once per second, when a car shows up that we haven't seen yet
+    async fn request_entry_list(&mut self) {
+        if self.last_entry_list_request.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+        self.last_entry_list_request = Instant::now();
+        if let Err(e) = self.socket.send(&encode_request_entry_list(self.connection_id)).await {
+            warn!("Could not request ACC entry list: {:?}", e);
+        }
+    }
+
+    fn apply_realtime_update(&mut self, update: &RealtimeUpdate) {
+        self.focused_car_index = u16::try_from(update.focused_car_index).ok();
+        let session = &mut self.telemetry.session;
+        session.kind = match update.session_type {
+            0 | 12 => SessionKind::Practice,
+            4 | 9 => SessionKind::Qualifying,
+            10 => SessionKind::Race,
+            11 | 13 => SessionKind::TimeTrial,
+            _ => SessionKind::Unknown,
+        };
+        session.phase = match update.phase {
+            1..=4 => SessionPhase::PreSession,
+            5 => SessionPhase::Running,
+            6 => SessionPhase::Finished,
+            7 | 8 => SessionPhase::PostSession,
+            _ => SessionPhase::Unknown,
+        };
+        session.time_left = Some(update.session_end_time_ms.max(0.0) / 1000.0);
+        session.air_temp = Some(update.ambient_temp as f32);
+        session.track_temp = Some(update.track_temp as f32);
+    }
+
+    fn apply_car_update(&mut self, update: &RealtimeCarUpdate) {
+        let telemetry = &mut self.telemetry;
+        telemetry.general.gear = update.gear as isize;
+        telemetry.general.speed = update.kmh as f32 / 3.6;
+
+        telemetry.timing = TelemetryTiming {
+            lap: Some(update.laps as u32 + 1),
+            position: if update.position > 0 { Some(update.position as u32) } else { None },
+            current_lap_time: update.current_lap.time(),
+            last_lap_time: update.last_lap.time(),
+            best_lap_time: update.best_session_lap.time(),
+            delta_to_best: Some(update.delta_ms as f32 / 1000.0),
+            lap_distance: self.track_meters.map(|meters| update.spline_position * meters),
+            location: match update.car_location {
+                1 => CarLocation::Track,
+                2 => CarLocation::PitLane,
+                3 => CarLocation::PitEntry,
+                4 => CarLocation::PitExit,
+                _ => CarLocation::Unknown,
+            },
+            ..Default::default()
+        };
+
+        // Only the position on the track map and the heading are available
+        telemetry.motion.heading = update.yaw;
+        telemetry.motion.position = [update.world_pos_x as f64, update.world_pos_y as f64, 0.0];
+    }
+}
+
+impl Drop for BackendAcc {
+    fn drop(&mut self) {
+        // Drop can't await, but a single datagram to a connected socket never blocks for long
+        if let Err(e) = self.socket.try_send(&encode_unregister(self.connection_id)) {
+            warn!("Could not unregister from ACC: {:?}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendAcc {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 2048];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            let message = match AccMessage::parse(&buf[..n]) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!("Dropping invalid ACC message: {e}");
+                    continue;
+                },
+            };
+            match message {
+                AccMessage::RealtimeUpdate(update) => {
+                    self.apply_realtime_update(&update);
+                    return Some(self.telemetry.clone());
+                },
+                AccMessage::RealtimeCarUpdate(update) => {
+                    if !self.known_cars.contains(&update.car_index) {
+                        self.request_entry_list().await;
+                    }
+                    if Some(update.car_index) == self.focused_car_index {
+                        self.apply_car_update(&update);
+                        return Some(self.telemetry.clone());
+                    }
+                },
+                AccMessage::EntryList(entry_list) => {
+                    self.known_cars = entry_list.car_indexes.into_iter().collect();
+                },
+                AccMessage::EntryListCar(car) => {
+                    self.known_cars.insert(car.car_index);
+                },
+                AccMessage::TrackData(track) => {
+                    debug!("ACC track: {} ({} m)", track.track_name, track.track_meters);
+                    self.track_meters = Some(track.track_meters as f32);
+                    self.telemetry.session.track_length = self.track_meters;
+                },
+                AccMessage::BroadcastingEvent(event) => debug!("ACC event: {}", event.message),
+                AccMessage::RegistrationResult(_) => {},
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::acc::tests::*;
+
+    async fn stand_in_server() -> (UdpSocket, AccConfig) {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = AccConfig {
+            port: server.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        (server, config)
+    }
+
+    #[tokio::test]
+    async fn follows_focused_car() {
+        let (server, config) = stand_in_server().await;
+        let server_task = tokio::spawn(async move {
+            let mut buf = [0u8; 256];
+            let mut received = Vec::new();
+            loop {
+                let (n, client) = server.recv_from(&mut buf).await.unwrap();
+                received.push(buf[0]);
+                match buf[0] {
+                    REGISTER_COMMAND_APPLICATION => {
+                        assert_eq!(&buf[..n], &encode_register("Dysoon Simhub", "asd", 100, "")[..]);
+                        server.send_to(&registration_result(5, true), client).await.unwrap();
+                    },
+                    REQUEST_TRACK_DATA => {
+                        // Replay a session: track data, then updates for another car and for the focused car
+                        for packet in [track_data(5), realtime_update(7), realtime_car_update(3), realtime_car_update(7)] {
+                            server.send_to(&packet, client).await.unwrap();
+                        }
+                    },
+                    UNREGISTER_COMMAND_APPLICATION => return received,
+                    _ => {},
+                }
+            }
+        });
+
+        let mut backend = BackendAcc::new(config).await.expect("registration should succeed");
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.session.kind, SessionKind::Race);
+        assert_eq!(telemetry.session.phase, SessionPhase::Running);
+        assert_eq!(telemetry.session.track_length, Some(5_793.0));
+
+        // The update for car 3 is skipped, car 7 is focused
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.general.gear, 4);
+        assert_eq!(telemetry.timing.position, Some(3));
+        assert_eq!(telemetry.timing.lap, Some(5));
+        assert_eq!(telemetry.timing.delta_to_best, Some(-0.35));
+        assert_eq!(telemetry.timing.lap_distance, Some(5_793.0 * 0.25));
+        assert_eq!(telemetry.timing.location, CarLocation::Track);
+        drop(backend);
+
+        let received = server_task.await.unwrap();
+        assert_eq!(received, [REGISTER_COMMAND_APPLICATION, REQUEST_ENTRY_LIST, REQUEST_TRACK_DATA, UNREGISTER_COMMAND_APPLICATION]);
+    }
+
+    #[tokio::test]
+    async fn refused_registration() {
+        let (server, config) = stand_in_server().await;
+        tokio::spawn(async move {
+            let mut buf = [0u8; 256];
+            let (_, client) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(&registration_result(-1, false), client).await.unwrap();
+        });
+        assert!(BackendAcc::new(config).await.is_none());
+    }
+}
@@ -0,0 +1,213 @@
+// Assetto Corsa only streams telemetry to clients that performed its handshake, so unlike the other games we connect
+// to it as a client. It only answers while a session is loaded; in menus the handshake times out and we retry later.
+// AC keeps sending updates until it receives a dismiss, which is sent when the backend is dropped.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use tokio::net::UdpSocket;
+
+use crate::telemetry::*;
+use crate::backend::protocols::assetto_corsa::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssettoCorsaConfig {
+    pub address: String,
+    pub port: u16,
+}
+
+impl Default for AssettoCorsaConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+pub struct BackendAssettoCorsa {
+    socket: UdpSocket,
+
+    telemetry: Telemetry,
+}
+
+impl BackendAssettoCorsa {
+    pub async fn new(config: AssettoCorsaConfig) -> Option<Self> {
+        let socket = match UdpSocket::bind("0.0.0.0:0").await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                return None;
+            },
+            Ok(socket) => socket,
+        };
+        if let Err(e) = socket.connect((config.address.as_str(), config.port)).await {
+            error!("Error: {:?}", e);
+            return None;
+        }
+
+        let handshake = match Self::handshake(&socket).await {
+            Ok(handshake) => handshake,
+            Err(e) => {
+                debug!("Assetto Corsa handshake failed, is a session loaded? {:?}", e);
+                return None;
+            },
+        };
+        debug!("Assetto Corsa handshake: {} in {} ({} {})", handshake.driver_name, handshake.car_name, handshake.track_name, handshake.track_config);
+
+        if let Err(e) = socket.send(&encode_handshaker(OPERATION_SUBSCRIBE_UPDATE)).await {
+            error!("Error: {:?}", e);
+            return None;
+        }
+
+        Some(Self {
+            socket,
+
+            telemetry: Telemetry {
+                game: "Assetto Corsa",
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn handshake(socket: &UdpSocket) -> anyhow::Result<HandshakeResponse> {
+        socket.send(&encode_handshaker(OPERATION_HANDSHAKE)).await?;
+        let mut buf = [0u8; 512];
+        let n = tokio::time::timeout(tokio::time::Duration::from_millis(500), socket.recv(&mut buf)).await??;
+        Ok(HandshakeResponse::parse(&buf[..n])?)
+    }
+
+    fn apply(&mut self, raw: &RtCarInfo) {
+        let telemetry = &mut self.telemetry;
+
+        telemetry.general.gear = raw.gear as isize - 1;
+        telemetry.general.speed = raw.speed_ms;
+        telemetry.engine.rpm = raw.engine_rpm.max(0.0) as usize;
+        telemetry.input = TelemetryInput {
+            throttle: raw.gas,
+            brake: raw.brake,
+            clutch: 1.0 - raw.clutch, // AC reports how far the clutch is engaged, not how far the pedal is pressed
+        };
+
+        telemetry.lights.available = DashLights::ABS | DashLights::TC | DashLights::PIT_SPEED;
+        telemetry.lights.active = DashLights::empty();
+        telemetry.lights.active.set(DashLights::ABS, raw.is_abs_in_action || !raw.is_abs_enabled);
+        telemetry.lights.active.set(DashLights::TC, raw.is_tc_in_action || !raw.is_tc_enabled);
+        telemetry.lights.active.set(DashLights::PIT_SPEED, raw.is_engine_limiter_on);
+
+        let lap_time = |t: i32| if t > 0 { Some(t as f32 / 1000.0) } else { None };
+        telemetry.timing = TelemetryTiming {
+            lap: Some(raw.lap_count.max(0) as u32 + 1),
+            current_lap_time: Some(raw.lap_time.max(0) as f32 / 1000.0),
+            last_lap_time: lap_time(raw.last_lap),
+            best_lap_time: lap_time(raw.best_lap),
+            ..Default::default()
+        };
+
+        // AC only reports G-forces relative to the car and no heading, so the heading is left at 0
+        // which makes world and car space line up for `TelemetryMotion::local_acceleration`
+        let [x, y, z] = raw.car_coordinates;
+        telemetry.motion = TelemetryMotion {
+            acceleration: [raw.acc_g_horizontal * 9.81, raw.acc_g_frontal * 9.81, raw.acc_g_vertical * 9.81],
+            position: [x as f64, z as f64, y as f64],
+            ..Default::default()
+        };
+
+        telemetry.wheels = (0..4).map(|i| TelemetryWheel {
+            suspension_travel: raw.suspension_height[i],
+            angular_velocity: raw.wheel_angular_speed[i],
+            speed: None,
+            vertical_load: raw.load[i],
+            slip_ratio: raw.slip_ratio[i],
+            slip_angle: raw.slip_angle[i].to_radians(),
+            tyre_temp: None,
+            tyre_pressure: None,
+            brake_temp: None,
+        }).collect();
+    }
+}
+
+impl Drop for BackendAssettoCorsa {
+    fn drop(&mut self) {
+        // Drop can't await, but a single datagram to a connected socket never blocks for long
+        if let Err(e) = self.socket.try_send(&encode_handshaker(OPERATION_DISMISS)) {
+            warn!("Could not dismiss Assetto Corsa telemetry: {:?}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendAssettoCorsa {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 512];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            match RtCarInfo::parse(&buf[..n]) {
+                Ok(raw) => {
+                    self.apply(&raw);
+                    return Some(self.telemetry.clone());
+                },
+                Err(e) => warn!("Dropping invalid Assetto Corsa packet: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::assetto_corsa::tests::{handshake_response, car_info};
+
+    fn operation(buf: &[u8]) -> i32 {
+        i32::from_le_bytes(buf[8..12].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn handshake_subscribe_and_dismiss() {
+        // Stand-in for AC's telemetry server
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = AssettoCorsaConfig {
+            address: "127.0.0.1".to_string(),
+            port: server.local_addr().unwrap().port(),
+        };
+        let server_task = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let mut operations = Vec::new();
+            loop {
+                let (n, client) = server.recv_from(&mut buf).await.unwrap();
+                operations.push(operation(&buf[..n]));
+                match operation(&buf[..n]) {
+                    OPERATION_HANDSHAKE => { server.send_to(&handshake_response(), client).await.unwrap(); },
+                    OPERATION_SUBSCRIBE_UPDATE => { server.send_to(&car_info(), client).await.unwrap(); },
+                    _ => return operations,
+                }
+            }
+        });
+
+        let mut backend = BackendAssettoCorsa::new(config).await.expect("handshake should succeed");
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.game, "Assetto Corsa");
+        assert_eq!(telemetry.general.gear, 3);
+        assert_eq!(telemetry.engine.rpm, 5_500);
+        assert_eq!(telemetry.timing.lap, Some(5));
+        assert_eq!(telemetry.timing.best_lap_time, Some(91.8));
+        assert!(telemetry.lights.is_on(DashLights::ABS));
+        assert!(!telemetry.lights.is_on(DashLights::TC));
+        drop(backend);
+
+        let operations = server_task.await.unwrap();
+        assert_eq!(operations, [OPERATION_HANDSHAKE, OPERATION_SUBSCRIBE_UPDATE, OPERATION_DISMISS]);
+    }
+
+    #[tokio::test]
+    async fn handshake_times_out_without_session() {
+        // Bound but never answering, like AC sitting in its menus
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = AssettoCorsaConfig {
+            address: "127.0.0.1".to_string(),
+            port: server.local_addr().unwrap().port(),
+        };
+        assert!(BackendAssettoCorsa::new(config).await.is_none());
+    }
+}
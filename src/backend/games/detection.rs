@@ -0,0 +1,308 @@
+// Finding running games. The preferred way is listening on every telemetry port at once and picking the game whose
+// decoder accepts the first packet, which also works when the game runs on another PC.
+// Games that only send telemetry when asked can't be heard that way, so they are found by their executable instead.
+// On Windows the process name is enough, but games running through Wine or Proton show up as `wine64-preloader` or
+// with their name cut off at 15 characters, so the executable path and command line are checked as well.
+// Windows paths in a command line can use either kind of slash.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use tokio::time::{Duration, Instant};
+
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectionConfig {
+    pub sniff_window_ms: u64,       // How long each detection cycle listens for telemetry
+    pub process_fallback: bool,     // Look for game executables if no telemetry was heard
+    pub process_interval_ms: u64,   // Minimum time between two looks at the process list
+    pub executables: ExecutableTable,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            sniff_window_ms: 250,
+            // Assetto Corsa, ACC and the truck games only send telemetry after a handshake, so they need this
+            process_fallback: true,
+            process_interval_ms: 2000,
+            executables: ExecutableTable::default(),
+        }
+    }
+}
+
+/// Executable names to look for, without `.exe`, for each internal game key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutableTable {
+    pub games: Vec<(String, Vec<String>)>,
+}
+
+impl Default for ExecutableTable {
+    fn default() -> Self {
+        let games: &[(&str, &[&str])] = &[
+            ("beamng", &["BeamNG.drive.x64"]),
+            ("lfs", &["LFS"]),
+            ("forza", &["forza_x64_release_final", "ForzaMotorsport", "ForzaHorizon4", "ForzaHorizon5"]), // The first one is Forza Motorsport 7
+            ("assetto_corsa", &["acs"]),
+            ("acc", &["AC2-Win64-Shipping"]),
+            ("pcars2", &["pCARS2", "pCARS2AVX64"]),
+            ("ams2", &["AMS2", "AMS2AVX"]),
+            ("dirt_rally_2", &["dirtrally2"]),
+            ("ea_wrc", &["WRC"]),
+            ("ets2", &["eurotrucks2"]),
+            ("ats", &["amtrucks"]),
+            ("rbr", &["RichardBurnsRally_SSE"]),
+        ];
+        Self {
+            games: games.iter().map(|(game, executables)| (game.to_string(), executables.iter().map(|e| e.to_string()).collect())).collect(),
+        }
+    }
+}
+
+impl ExecutableTable {
+    fn find(&self, executable: &str) -> Option<(&str, &str)> {
+        self.games.iter().find_map(|(game, executables)| {
+            executables.iter().find(|e| e.eq_ignore_ascii_case(executable)).map(|e| (game.as_str(), e.as_str()))
+        })
+    }
+}
+
+/// How a running game was found
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetectionRule {
+    ProcessName(String),
+    ExecutablePath(String),
+    CommandLine(String),
+    Packet(u16),
+}
+
+impl std::fmt::Display for DetectionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::ProcessName(name) => write!(f, "process name {name}"),
+            Self::ExecutablePath(name) => write!(f, "executable path ({name})"),
+            Self::CommandLine(name) => write!(f, "command line ({name})"),
+            Self::Packet(port) => write!(f, "telemetry on port {port}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedGame {
+    pub game: String,
+    pub rule: DetectionRule,
+}
+
+/// The file name of a Windows or Unix path, without `.exe`
+fn executable_name(path: &str) -> &str {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    strip_exe(name).unwrap_or(name)
+}
+
+/// The name without its `.exe` suffix, if it has one
+fn strip_exe(name: &str) -> Option<&str> {
+    match name.len().checked_sub(4) {
+        Some(i) if name.is_char_boundary(i) && name[i..].eq_ignore_ascii_case(".exe") => Some(&name[..i]),
+        _ => None,
+    }
+}
+
+/// Checks a single process against the table, trying the most specific rule first
+fn match_process(table: &ExecutableTable, name: &str, exe: &Path, cmd: &[String]) -> Option<DetectedGame> {
+    if let Some((game, executable)) = table.find(executable_name(name)) {
+        return Some(DetectedGame { game: game.to_string(), rule: DetectionRule::ProcessName(executable.to_string()) });
+    }
+    if let Some((game, executable)) = exe.to_str().and_then(|exe| table.find(executable_name(exe))) {
+        return Some(DetectedGame { game: game.to_string(), rule: DetectionRule::ExecutablePath(executable.to_string()) });
+    }
+    // Wine and Proton pass the Windows executable, always with its `.exe`, as an argument to their loader.
+    // Without the suffix it's likely just a file or folder named like the game, like `ls ~/Games/LFS`.
+    cmd.iter().filter(|arg| strip_exe(arg).is_some()).find_map(|arg| table.find(executable_name(arg))).map(|(game, executable)| DetectedGame {
+        game: game.to_string(),
+        rule: DetectionRule::CommandLine(executable.to_string()),
+    })
+}
+
+/// Keeps the process list around between detection cycles, building it from scratch is expensive
+#[derive(Default)]
+pub struct ProcessList {
+    system: Option<sysinfo::System>,
+    last_refresh: Option<Instant>,
+}
+
+impl ProcessList {
+    /// Every supported game that is running, once per game.
+    /// Returns nothing if the list was refreshed less than `interval` ago.
+    pub fn find_running_supported_games(&mut self, table: &ExecutableTable, interval: Duration) -> Vec<DetectedGame> {
+        use sysinfo::{ProcessExt, SystemExt};
+
+        if self.last_refresh.is_some_and(|last| last.elapsed() < interval) {
+            return Vec::new();
+        }
+        self.last_refresh = Some(Instant::now());
+        let system = self.system.get_or_insert_with(sysinfo::System::new);
+        system.refresh_processes();
+
+        let mut detected: Vec<DetectedGame> = Vec::new();
+        for process in system.processes().values() {
+            if let Some(game) = match_process(table, process.name(), process.exe(), process.cmd()) {
+                if !detected.iter().any(|d| d.game == game.game) {
+                    detected.push(game);
+                }
+            }
+        }
+        detected
+    }
+}
+
+/// Decides whether a packet comes from the rule's game
+pub type PacketValidator = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// A game that can be recognised from the packets arriving on its telemetry port
+#[derive(Clone)]
+pub struct SniffRule {
+    pub game: &'static str,
+    pub listen: ListenConfig,
+    pub validate: PacketValidator,
+}
+
+impl SniffRule {
+    pub fn new(game: &'static str, listen: &ListenConfig, validate: impl Fn(&[u8]) -> bool + Send + Sync + 'static) -> Self {
+        Self { game, listen: listen.clone(), validate: Arc::new(validate) }
+    }
+}
+
+/// Listens on every rule's port at once and returns the first game whose packet validates.
+/// Rules sharing a port are tried in order, and the first of them decides how the port is bound.
+/// Every port is released again before this returns, so the game's backend can bind it.
+/// A port that can't be bound is only warned about the first time, it's recorded in `unbindable`.
+pub async fn sniff(rules: &[SniffRule], window: Duration, unbindable: &mut HashSet<u16>) -> Option<DetectedGame> {
+    let mut ports: Vec<u16> = rules.iter().map(|rule| rule.listen.port).collect();
+    ports.sort_unstable();
+    ports.dedup();
+
+    let mut sockets = Vec::new();
+    for port in ports {
+        let rules: Vec<SniffRule> = rules.iter().filter(|rule| rule.listen.port == port).cloned().collect();
+        match rules[0].listen.bind().await {
+            Err(e) if unbindable.insert(port) => warn!("Could not bind port {port} to listen for telemetry: {e}"),
+            Err(e) => trace!("Could not bind port {port} to listen for telemetry: {:?}", e),
+            Ok(socket) => sockets.push((port, socket, rules)),
+        }
+    }
+    sniff_sockets(sockets, window).await
+}
+
+/// Listens on already bound ports, each with the rules for the games that send to it
+async fn sniff_sockets(sockets: Vec<(u16, TelemetrySocket, Vec<SniffRule>)>, window: Duration) -> Option<DetectedGame> {
+    let mut listeners = JoinSet::new();
+    for (port, socket, rules) in sockets {
+        listeners.spawn(async move {
+            let mut buf = [0u8; 2048];
+            loop {
+                let n = socket.recv(&mut buf).await.ok()?;
+                match rules.iter().find(|rule| (rule.validate)(&buf[..n])) {
+                    Some(rule) => return Some(DetectedGame { game: rule.game.to_string(), rule: DetectionRule::Packet(port) }),
+                    None => trace!("Unrecognised packet on port {port}"),
+                }
+            }
+        });
+    }
+
+    let detected = tokio::time::timeout(window, async {
+        while let Some(result) = listeners.join_next().await {
+            if let Ok(Some(detected)) = result {
+                return Some(detected);
+            }
+        }
+        None
+    }).await.ok().flatten();
+    listeners.shutdown().await;
+    detected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(name: &str, exe: &str, cmd: &[&str]) -> Option<DetectedGame> {
+        let cmd: Vec<String> = cmd.iter().map(|s| s.to_string()).collect();
+        match_process(&ExecutableTable::default(), name, Path::new(exe), &cmd)
+    }
+
+    #[test]
+    fn windows_process_name() {
+        let detected = detect("BeamNG.drive.x64.exe", "C:\\Games\\BeamNG.drive\\Bin64\\BeamNG.drive.x64.exe", &[]).unwrap();
+        assert_eq!(detected.game, "beamng");
+        assert_eq!(detected.rule, DetectionRule::ProcessName("BeamNG.drive.x64".to_string()));
+        assert_eq!(detect("explorer.exe", "C:\\Windows\\explorer.exe", &[]), None);
+    }
+
+    #[test]
+    fn proton_command_line() {
+        // Linux cuts the process name off at 15 characters, and the executable is Wine's loader
+        let detected = detect(
+            "BeamNG.drive.x6",
+            "/home/rig/.steam/steam/steamapps/common/Proton 8.0/dist/bin/wine64-preloader",
+            &["/home/rig/.steam/steam/steamapps/common/Proton 8.0/dist/bin/wine64", "Z:\\home\\rig\\Games\\BeamNG.drive\\Bin64\\BeamNG.drive.x64.exe", "-nosteam"],
+        ).unwrap();
+        assert_eq!(detected.game, "beamng");
+        assert_eq!(detected.rule, DetectionRule::CommandLine("BeamNG.drive.x64".to_string()));
+
+        let detected = detect("wine64-preloader", "/usr/bin/wine64-preloader", &["c:/rsf/RichardBurnsRally_SSE.EXE"]).unwrap();
+        assert_eq!(detected.game, "rbr");
+    }
+
+    #[test]
+    fn arguments_without_exe() {
+        assert_eq!(detect("vim", "/usr/bin/vim", &["vim", "WRC"]), None);
+        assert_eq!(detect("ls", "/usr/bin/ls", &["ls", "/home/rig/Games/LFS"]), None);
+        assert_eq!(detect("tar", "/usr/bin/tar", &["tar", "xf", "AMS2.tar", "acs"]), None);
+    }
+
+    #[test]
+    fn executable_path() {
+        let detected = detect("MainThrd", "/opt/games/lfs/LFS.exe", &[]).unwrap();
+        assert_eq!(detected.game, "lfs");
+        assert_eq!(detected.rule, DetectionRule::ExecutablePath("LFS".to_string()));
+    }
+
+    #[tokio::test]
+    async fn sniff_first_valid_packet() {
+        let socket = ListenConfig::local(0).bind().await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let listen = ListenConfig::local(port);
+        let rules = [
+            SniffRule::new("short", &listen, |buf| buf.len() == 4),
+            SniffRule::new("long", &listen, |buf| buf.len() == 8),
+        ];
+
+        let game = tokio::spawn(async move {
+            let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            socket.send_to(&[0; 3], ("127.0.0.1", port)).await.unwrap();
+            socket.send_to(&[0; 8], ("127.0.0.1", port)).await.unwrap();
+        });
+        let detected = sniff_sockets(vec![(port, socket, rules.to_vec())], Duration::from_millis(500)).await.unwrap();
+        game.await.unwrap();
+        assert_eq!(detected, DetectedGame { game: "long".to_string(), rule: DetectionRule::Packet(port) });
+
+        // The port has to be free for the backend again, and a taken port is skipped
+        let _taken = std::net::UdpSocket::bind(("127.0.0.1", port)).unwrap();
+        let mut unbindable = HashSet::new();
+        assert_eq!(sniff(&rules, Duration::from_millis(20), &mut unbindable).await, None);
+        assert_eq!(unbindable, HashSet::from([port]));
+    }
+
+    #[test]
+    fn custom_table() {
+        let mut table = ExecutableTable::default();
+        table.games.push(("beamng".to_string(), vec!["BeamNG.tech.x64".to_string()]));
+        let detected = match_process(&table, "BeamNG.tech.x64.exe", Path::new(""), &[]).unwrap();
+        assert_eq!(detected.game, "beamng");
+    }
+}
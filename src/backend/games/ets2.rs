@@ -0,0 +1,278 @@
+// Euro Truck Simulator 2 and American Truck Simulator don't send telemetry themselves. The community telemetry server
+// (github.com/Funbit/ets2-telemetry-server) hooks into the game and serves its state as JSON over HTTP, which is polled here.
+// The server answers with a plain HTTP/1.0 response, so a bare request over a TCP stream is enough.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{Duration, Instant};
+
+use crate::telemetry::*;
+use crate::backend::protocols::ets2::*;
+
+/// How long the server gets to answer, detection waits for it when looking for a game
+const FETCH_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ets2Config {
+    pub address: String,
+    pub port: u16,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for Ets2Config {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+            poll_interval_ms: 50,
+        }
+    }
+}
+
+pub struct BackendEts2 {
+    config: Ets2Config,
+    next_poll: Instant,
+
+    telemetry: Telemetry,
+}
+
+impl BackendEts2 {
+    /// Only returns a backend if the telemetry server answers and the game is running
+    pub async fn new(config: Ets2Config) -> Option<Self> {
+        let backend = Self {
+            config,
+            next_poll: Instant::now(),
+
+            telemetry: Telemetry {
+                game: "Euro Truck Simulator 2",
+                ..Default::default()
+            },
+        };
+        let body = match backend.fetch().await {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Could not reach the ETS2 telemetry server: {:?}", e);
+                return None;
+            },
+        };
+        // The server keeps answering from the menu, a backend now would only time out again and again
+        match DataEts2::parse(&body) {
+            Ok(raw) if raw.game.connected => Some(backend),
+            Ok(_) => {
+                debug!("ETS2 telemetry server is running, but the game isn't");
+                None
+            },
+            Err(e) => {
+                warn!("Invalid ETS2 telemetry: {e}");
+                None
+            },
+        }
+    }
+
+    /// Requests the latest telemetry from the server, giving up if it doesn't answer in time
+    async fn fetch(&self) -> std::io::Result<String> {
+        tokio::time::timeout(FETCH_TIMEOUT, self.request()).await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "No answer from the telemetry server"))?
+    }
+
+    async fn request(&self) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect((self.config.address.as_str(), self.config.port)).await?;
+        let request = format!("GET {TELEMETRY_PATH} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n", self.config.address);
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response.split_once("\r\n\r\n").ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "Incomplete HTTP response"))?;
+        let status = head.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Unexpected HTTP status: {status}")));
+        }
+        Ok(body.to_string())
+    }
+
+    fn apply(&mut self, raw: &DataEts2) {
+        let telemetry = &mut self.telemetry;
+        let truck = &raw.truck;
+        let american = raw.game.game_name.as_deref() == Some("ATS");
+
+        telemetry.game = if american { "American Truck Simulator" } else { "Euro Truck Simulator 2" };
+        telemetry.units = TelemetryUnits {
+            prefers_miles: american,
+            prefers_psi: american,
+        };
+
+        telemetry.general = TelemetryGeneral {
+            gear: truck.displayed_gear as isize,
+            fuel: if truck.fuel_capacity > 0.0 { truck.fuel / truck.fuel_capacity } else { 0.0 },
+            fuel_capacity: if truck.fuel_capacity > 0.0 { Some(truck.fuel_capacity) } else { None },
+            speed: truck.speed.abs() / 3.6,
+        };
+        telemetry.engine.rpm = truck.engine_rpm.max(0.0) as usize;
+        telemetry.engine.max_rpm = if truck.engine_rpm_max > 0.0 { Some(truck.engine_rpm_max as usize) } else { None };
+        telemetry.engine.engine_temp = Some(truck.water_temperature);
+        telemetry.engine.oil_temp = Some(truck.oil_temperature);
+        telemetry.engine.oil_pressure = Some(truck.oil_pressure / 14.5038);
+        telemetry.input = TelemetryInput {
+            throttle: truck.game_throttle,
+            brake: truck.game_brake,
+            clutch: truck.game_clutch,
+        };
+
+        telemetry.lights.available = DashLights::SIGNAL_LEFT | DashLights::SIGNAL_RIGHT | DashLights::SIDELIGHTS | DashLights::DIPPED | DashLights::FULL_BEAM
+            | DashLights::HANDBRAKE | DashLights::FUEL_WARNING | DashLights::OIL_WARNING | DashLights::BATTERY;
+        for (on, light) in [
+            (truck.blinker_left_on, DashLights::SIGNAL_LEFT),
+            (truck.blinker_right_on, DashLights::SIGNAL_RIGHT),
+            (truck.lights_parking_on, DashLights::SIDELIGHTS),
+            (truck.lights_beam_low_on, DashLights::DIPPED),
+            (truck.lights_beam_high_on, DashLights::FULL_BEAM),
+            (truck.park_brake_on, DashLights::HANDBRAKE),
+            (truck.fuel_warning_on, DashLights::FUEL_WARNING),
+            (truck.oil_pressure_warning_on, DashLights::OIL_WARNING),
+            (truck.battery_voltage_warning_on, DashLights::BATTERY),
+        ] {
+            telemetry.lights.active.set(light, on);
+        }
+
+        // SCS uses y up and z south, with headings as a fraction of a full turn
+        let placement = &truck.placement;
+        let heading = placement.heading * std::f32::consts::TAU;
+        let (sin, cos) = heading.sin_cos();
+        let (lateral, longitudinal) = (truck.acceleration.x, -truck.acceleration.z);
+        telemetry.motion = TelemetryMotion {
+            heading,
+            pitch: placement.pitch * std::f32::consts::TAU,
+            roll: placement.roll * std::f32::consts::TAU,
+            acceleration: [lateral * cos - longitudinal * sin, lateral * sin + longitudinal * cos, truck.acceleration.y],
+            position: [placement.x as f64, -placement.z as f64, placement.y as f64],
+            ..Default::default() // Velocities aren't reported
+        };
+
+        telemetry.truck = Some(TelemetryTruck {
+            air_pressure: truck.air_pressure / 14.5038,
+            air_pressure_warning: truck.air_pressure_warning_on || truck.air_pressure_emergency_on,
+            cruise_control: if truck.cruise_control_on { Some(truck.cruise_control_speed / 3.6) } else { None },
+            retarder_step: truck.retarder_brake,
+            retarder_steps: truck.retarder_step_count,
+            engine_brake: truck.motor_brake_on,
+            navigation_distance: if raw.navigation.estimated_distance > 0.0 { Some(raw.navigation.estimated_distance) } else { None },
+            speed_limit: if raw.navigation.speed_limit > 0.0 { Some(raw.navigation.speed_limit / 3.6) } else { None },
+        });
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendEts2 {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        loop {
+            tokio::time::sleep_until(self.next_poll).await;
+            self.next_poll = Instant::now() + Duration::from_millis(self.config.poll_interval_ms);
+
+            let body = match self.fetch().await {
+                Ok(body) => body,
+                Err(e) => {
+                    debug!("ETS2 telemetry server stopped answering: {:?}", e);
+                    return None;
+                },
+            };
+            match DataEts2::parse(&body) {
+                // The server keeps answering while the game isn't running, so let the backend time out instead
+                Ok(raw) if !raw.game.connected => continue,
+                Ok(raw) => {
+                    self.apply(&raw);
+                    return Some(self.telemetry.clone());
+                },
+                Err(e) => warn!("Dropping invalid ETS2 telemetry: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::ets2::tests::DRIVING;
+
+    /// Answers every request with the given status and body, and counts the requests
+    async fn stand_in_server(status: &'static str, body: &'static str) -> (Ets2Config, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Ets2Config {
+            port: listener.local_addr().unwrap().port(),
+            poll_interval_ms: 10,
+            ..Default::default()
+        };
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 512];
+                let n = stream.read(&mut buf).await.unwrap();
+                let _ = tx.send(String::from_utf8_lossy(&buf[..n]).into_owned());
+                let response = format!("HTTP/1.0 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}", body.len());
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (config, rx)
+    }
+
+    #[tokio::test]
+    async fn polls_telemetry_server() {
+        let (config, mut requests) = stand_in_server("200 OK", DRIVING).await;
+        let mut backend = BackendEts2::new(config).await.expect("server should answer");
+        let telemetry = backend.next_event().await.unwrap();
+        assert!(requests.recv().await.unwrap().starts_with("GET /api/ets2/telemetry HTTP/1.0\r\n"));
+
+        assert_eq!(telemetry.game, "American Truck Simulator");
+        assert!(telemetry.units.prefers_miles);
+        assert_eq!(telemetry.general.gear, 11);
+        assert_eq!(telemetry.general.fuel, 0.5);
+        assert_eq!(telemetry.engine.rpm, 1380);
+        assert_eq!(telemetry.engine.max_rpm, Some(2100));
+        assert!(telemetry.lights.is_on(DashLights::SIGNAL_LEFT));
+        assert!(!telemetry.lights.is_on(DashLights::SIGNAL_RIGHT));
+        assert!((telemetry.motion.heading - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        let truck = telemetry.truck.unwrap();
+        assert!((truck.air_pressure - 120.5 / 14.5038).abs() < 1e-6);
+        assert_eq!(truck.cruise_control, Some(88.0 / 3.6));
+        assert_eq!((truck.retarder_step, truck.retarder_steps), (1, 3));
+        assert_eq!(truck.navigation_distance, Some(184320.0));
+    }
+
+    #[tokio::test]
+    async fn server_errors() {
+        let (config, _requests) = stand_in_server("404 Not Found", "").await;
+        assert!(BackendEts2::new(config).await.is_none());
+
+        let config = Ets2Config {
+            port: 1, // Nothing listens here
+            ..Default::default()
+        };
+        assert!(BackendEts2::new(config).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn game_not_running() {
+        let (config, _requests) = stand_in_server("200 OK", r#"{ "game": { "connected": false, "gameName": null }, "truck": {}, "navigation": {} }"#).await;
+        assert!(BackendEts2::new(config).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn silent_server() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Ets2Config { port: listener.local_addr().unwrap().port(), ..Default::default() };
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+        let backend = tokio::time::timeout(FETCH_TIMEOUT * 4, BackendEts2::new(config)).await.expect("detection must not hang");
+        assert!(backend.is_none());
+    }
+}
@@ -0,0 +1,194 @@
+// F1 23 and F1 24 stream their telemetry over UDP as soon as it's enabled in the game's telemetry settings.
+// The game is often run through Proton, where its process name is unreliable, so it is detected from the packets instead:
+// if something on the F1 port sends a valid 2023/2024 header, the game is running.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::f1::*;
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct F1Config {
+    pub listen: ListenConfig,
+}
+
+impl Default for F1Config {
+    fn default() -> Self {
+        Self {
+            listen: ListenConfig::local(DEFAULT_PORT),
+        }
+    }
+}
+
+pub struct BackendF1 {
+    socket: TelemetrySocket,
+
+    telemetry: Telemetry,
+}
+
+impl BackendF1 {
+    /// Listens on the F1 port for a short while and only returns a backend if a valid F1 packet arrives.
+    pub async fn detect(config: F1Config) -> Option<Self> {
+        let socket = match config.listen.bind().await {
+            Err(e) => {
+                // This runs on every detection cycle, so don't flood the log if another program holds the port
+                debug!("Could not bind F1 port: {:?}", e);
+                return None;
+            },
+            Ok(socket) => socket,
+        };
+
+        let mut buf = [0u8; 2048];
+        let n = tokio::time::timeout(tokio::time::Duration::from_millis(50), socket.recv(&mut buf)).await.ok()?.ok()?;
+        let header = match PacketHeader::parse(&buf[..n]) {
+            Ok(header) => header,
+            Err(e) => {
+                trace!("Not an F1 packet: {e}");
+                return None;
+            },
+        };
+
+        let mut backend = Self {
+            socket,
+
+            telemetry: Telemetry {
+                game: if header.packet_format == 2023 { "F1 23" } else { "F1 24" },
+                ..Default::default()
+            },
+        };
+        backend.handle_packet(&buf[..n]);
+        Some(backend)
+    }
+
+    /// Merges a packet into the latest telemetry, only looking at the player's car.
+    /// Returns false if the packet couldn't be decoded.
+    fn handle_packet(&mut self, buf: &[u8]) -> bool {
+        let raw = match DataF1::parse(buf) {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!("Dropping invalid F1 packet: {e}");
+                return false;
+            },
+        };
+        let player = raw.header.player_car_index as usize;
+        let telemetry = &mut self.telemetry;
+
+        match raw.packet {
+            F1Packet::Motion(cars) => {
+                let car = &cars[player];
+                // F1 uses y up and z forward, we use z up and y forward
+                let [x, y, z] = car.world_position;
+                let [vx, vy, vz] = car.world_velocity;
+                let forward = car.world_forward_dir.map(|v| v as f32 / 32767.0);
+                let right = car.world_right_dir.map(|v| v as f32 / 32767.0);
+                let acceleration = [
+                    (car.g_force_lateral * right[0] + car.g_force_longitudinal * forward[0]) * 9.81,
+                    (car.g_force_lateral * right[2] + car.g_force_longitudinal * forward[2]) * 9.81,
+                    car.g_force_vertical * 9.81,
+                ];
+                telemetry.motion = TelemetryMotion {
+                    heading: car.yaw,
+                    pitch: car.pitch,
+                    roll: car.roll,
+                    angular_velocity: [0.0; 3], // Only available in the extended motion packet
+                    acceleration,
+                    velocity: [vx, vz, vy],
+                    position: [x as f64, z as f64, y as f64],
+                };
+            },
+            F1Packet::Session(session) => {
+                let format = raw.header.packet_format;
+                telemetry.session = TelemetrySession {
+                    kind: match (format, session.session_type) {
+                        (_, 1..=4) => SessionKind::Practice,
+                        (_, 5..=9) => SessionKind::Qualifying,
+                        (2023, 10..=12) => SessionKind::Race,
+                        (2023, 13) => SessionKind::TimeTrial,
+                        (_, 10..=14) => SessionKind::Qualifying, // Sprint shootouts
+                        (_, 15..=17) => SessionKind::Race,
+                        (_, 18) => SessionKind::TimeTrial,
+                        _ => SessionKind::Unknown,
+                    },
+                    total_laps: Some(session.total_laps as u32),
+                    time_left: Some(session.session_time_left as f32),
+                    track_length: Some(session.track_length as f32),
+                    air_temp: Some(session.air_temperature as f32),
+                    track_temp: Some(session.track_temperature as f32),
+                    ..Default::default()
+                };
+            },
+            F1Packet::LapData(cars) => {
+                let lap = &cars[player];
+                telemetry.timing = TelemetryTiming {
+                    lap: Some(lap.current_lap_num as u32),
+                    position: Some(lap.car_position as u32),
+                    current_lap_time: Some(lap.current_lap_time_ms as f32 / 1000.0),
+                    last_lap_time: if lap.last_lap_time_ms > 0 { Some(lap.last_lap_time_ms as f32 / 1000.0) } else { None },
+                    best_lap_time: telemetry.timing.best_lap_time, // Only available in the session history packet
+                    delta_to_car_ahead: Some(lap.delta_to_car_in_front_ms as f32 / 1000.0),
+                    delta_to_leader: Some(lap.delta_to_race_leader_ms as f32 / 1000.0),
+                    delta_to_best: None,
+                    lap_distance: Some(lap.lap_distance),
+                    location: if lap.pit_status == 0 { CarLocation::Track } else { CarLocation::PitLane },
+                };
+            },
+            F1Packet::CarTelemetry(cars) => {
+                let car = &cars[player];
+                telemetry.general.gear = car.gear as isize;
+                telemetry.general.speed = car.speed as f32 / 3.6;
+                telemetry.engine.rpm = car.engine_rpm as usize;
+                telemetry.engine.engine_temp = Some(car.engine_temperature as f32);
+                telemetry.input = TelemetryInput {
+                    throttle: car.throttle,
+                    brake: car.brake,
+                    clutch: car.clutch as f32 / 100.0,
+                };
+                telemetry.lights.rev_lights = Some(car.rev_lights_percent as f32 / 100.0);
+                telemetry.drs.get_or_insert_with(Default::default).active = car.drs;
+                // F1 orders the wheels rear left, rear right, front left, front right
+                telemetry.wheels.resize_with(4, Default::default);
+                for (wheel, i) in telemetry.wheels.iter_mut().zip([2, 3, 0, 1]) {
+                    wheel.tyre_temp = Some(car.tyres_surface_temperature[i] as f32);
+                    wheel.tyre_pressure = Some(car.tyres_pressure[i] / 14.5038);
+                    wheel.brake_temp = Some(car.brakes_temperature[i] as f32);
+                }
+            },
+            F1Packet::CarStatus(cars) => {
+                let car = &cars[player];
+                telemetry.general.fuel = if car.fuel_capacity > 0.0 { car.fuel_in_tank / car.fuel_capacity } else { 0.0 };
+                telemetry.general.fuel_capacity = if car.fuel_capacity > 0.0 { Some(car.fuel_capacity) } else { None };
+                telemetry.engine.max_rpm = Some(car.max_rpm as usize);
+                telemetry.engine.idle_rpm = Some(car.idle_rpm as usize);
+                telemetry.lights.available = DashLights::PIT_SPEED | DashLights::TC | DashLights::ABS;
+                telemetry.lights.active.set(DashLights::PIT_SPEED, car.pit_limiter_status);
+                telemetry.lights.active.set(DashLights::TC, car.traction_control == 0); // Like OutGauge, lit when switched off
+                telemetry.lights.active.set(DashLights::ABS, !car.anti_lock_brakes);
+                telemetry.drs.get_or_insert_with(Default::default).allowed = car.drs_allowed;
+                telemetry.ers = Some(TelemetryErs {
+                    store: car.ers_store_energy / ERS_MAX_ENERGY,
+                    deploy_mode: car.ers_deploy_mode,
+                    deployed_this_lap: car.ers_deployed_this_lap / ERS_MAX_ENERGY,
+                });
+            },
+            F1Packet::Other => return false,
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendF1 {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 2048];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            if self.handle_packet(&buf[..n]) {
+                return Some(self.telemetry.clone());
+            }
+        }
+    }
+}
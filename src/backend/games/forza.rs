@@ -0,0 +1,135 @@
+// Forza Motorsport and Forza Horizon both speak "Data Out", enabled in the HUD/gameplay settings with a target IP and port.
+// The port has no default in the game, so it has to match `ForzaConfig::port`.
+// Acceleration, velocity and angular velocity are reported in car space, they are rotated into world space using the yaw.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::forza::*;
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForzaConfig {
+    pub listen: ListenConfig,
+}
+
+impl Default for ForzaConfig {
+    fn default() -> Self {
+        Self {
+            listen: ListenConfig::local(5300),
+        }
+    }
+}
+
+pub struct BackendForza {
+    socket: TelemetrySocket,
+
+    telemetry: Telemetry,
+}
+
+impl BackendForza {
+    pub async fn new(config: ForzaConfig) -> Option<Self> {
+        match config.listen.bind().await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            },
+            Ok(socket) => {
+                Some(Self {
+                    socket,
+
+                    telemetry: Telemetry {
+                        game: "Forza",
+                        ..Default::default()
+                    },
+                })
+            }
+        }
+    }
+
+    fn apply(&mut self, raw: &DataForza, len: usize) {
+        let telemetry = &mut self.telemetry;
+        let sled = &raw.sled;
+
+        telemetry.game = if len == HORIZON_DASH_SIZE { "Forza Horizon" } else { "Forza Motorsport" };
+
+        telemetry.engine.rpm = sled.current_engine_rpm as usize;
+        telemetry.engine.max_rpm = if sled.engine_max_rpm > 0.0 { Some(sled.engine_max_rpm as usize) } else { None };
+        telemetry.engine.idle_rpm = if sled.engine_idle_rpm > 0.0 { Some(sled.engine_idle_rpm as usize) } else { None };
+
+        let (sin, cos) = sled.yaw.sin_cos();
+        let to_world = |[x, y, z]: [f32; 3]| [x * cos - z * sin, x * sin + z * cos, y];
+        telemetry.motion.heading = sled.yaw;
+        telemetry.motion.pitch = sled.pitch;
+        telemetry.motion.roll = sled.roll;
+        telemetry.motion.angular_velocity = to_world(sled.angular_velocity);
+        telemetry.motion.acceleration = to_world(sled.acceleration);
+        telemetry.motion.velocity = to_world(sled.velocity);
+
+        telemetry.wheels = (0..4).map(|i| TelemetryWheel {
+            suspension_travel: sled.suspension_travel_meters[i],
+            angular_velocity: sled.wheel_rotation_speed[i],
+            speed: None,
+            vertical_load: 0.0, // Not reported
+            slip_ratio: sled.tire_slip_ratio[i],
+            slip_angle: 0.0,    // Not reported, Forza only sends a normalised grip value instead of an angle
+            tyre_temp: raw.dash.as_ref().map(|dash| (dash.tire_temp[i] - 32.0) * 5.0 / 9.0),
+            tyre_pressure: None,
+            brake_temp: None,
+        }).collect();
+
+        match &raw.dash {
+            Some(dash) => {
+                telemetry.general = TelemetryGeneral {
+                    gear: if dash.gear == 0 { -1 } else { dash.gear as isize },
+                    fuel: dash.fuel,
+                    fuel_capacity: None,
+                    speed: dash.speed,
+                };
+                telemetry.engine.turbo = Some(dash.boost / 14.5038);
+                telemetry.input = TelemetryInput {
+                    throttle: dash.accel as f32 / 255.0,
+                    brake: dash.brake as f32 / 255.0,
+                    clutch: dash.clutch as f32 / 255.0,
+                };
+                let [x, y, z] = dash.position;
+                telemetry.motion.position = [x as f64, z as f64, y as f64];
+                let lap_time = |t: f32| if t > 0.0 { Some(t) } else { None };
+                telemetry.timing = TelemetryTiming {
+                    lap: Some(dash.lap_number as u32 + 1),
+                    position: if dash.race_position > 0 { Some(dash.race_position as u32) } else { None },
+                    current_lap_time: Some(dash.current_lap),
+                    last_lap_time: lap_time(dash.last_lap),
+                    best_lap_time: lap_time(dash.best_lap),
+                    ..Default::default()
+                };
+            },
+            None => {
+                // The sled format has no dashboard, but the speed can still be derived
+                let [x, y, z] = sled.velocity;
+                telemetry.general.speed = (x * x + y * y + z * z).sqrt();
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendForza {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        // Larger than any data out packet, so unknown formats show up as a length error instead of being cut off
+        let mut buf = [0u8; 512];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            match DataForza::parse(&buf[..n]) {
+                Ok(raw) => {
+                    self.apply(&raw, n);
+                    return Some(self.telemetry.clone());
+                },
+                Err(e) => warn!("Dropping invalid forza packet: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// Gran Turismo 7 runs on a console, so there is no process to look for. Instead a heartbeat is sent to the console,
+// and if encrypted packets with the right magic come back, the game is running.
+// The console sends its packets back to wherever the heartbeat came from, so the same socket is used for both.
+// Without a configured console address the heartbeat is broadcast to the local network.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, Instant};
+
+use crate::telemetry::*;
+use crate::backend::protocols::gt7::*;
+use super::listen::{ListenConfig, TelemetrySocket};
+
+/// The console stops sending after a few seconds without a heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gt7Config {
+    pub console_address: String,
+    pub heartbeat_port: u16,
+    pub listen: ListenConfig,
+}
+
+impl Default for Gt7Config {
+    fn default() -> Self {
+        Self {
+            console_address: "255.255.255.255".to_string(),
+            heartbeat_port: HEARTBEAT_PORT,
+            listen: ListenConfig::any(DEFAULT_PORT),
+        }
+    }
+}
+
+pub struct BackendGt7 {
+    socket: TelemetrySocket,
+    config: Gt7Config,
+    last_heartbeat: Instant,
+
+    telemetry: Telemetry,
+}
+
+impl BackendGt7 {
+    /// Sends a heartbeat and only returns a backend if a valid GT7 packet comes back shortly after.
+    pub async fn detect(config: Gt7Config) -> Option<Self> {
+        let socket = match config.listen.bind().await {
+            Err(e) => {
+                // This runs on every detection cycle, so don't flood the log if another program holds the port
+                debug!("Could not bind GT7 port: {:?}", e);
+                return None;
+            },
+            Ok(socket) => socket,
+        };
+        if let Err(e) = socket.set_broadcast(true) {
+            debug!("Could not enable broadcasting for the GT7 heartbeat: {:?}", e);
+        }
+
+        let mut backend = Self {
+            socket,
+            config,
+            last_heartbeat: Instant::now(),
+
+            telemetry: Telemetry {
+                game: "Gran Turismo 7",
+                ..Default::default()
+            },
+        };
+        backend.send_heartbeat().await;
+
+        let mut buf = [0u8; 512];
+        let n = tokio::time::timeout(Duration::from_millis(50), backend.socket.recv(&mut buf)).await.ok()?.ok()?;
+        match DataGt7::parse(&buf[..n]) {
+            Ok(raw) => backend.apply(&raw),
+            Err(e) => {
+                trace!("Not a GT7 packet: {e}");
+                return None;
+            },
+        }
+        Some(backend)
+    }
+
+    async fn send_heartbeat(&mut self) {
+        self.last_heartbeat = Instant::now();
+        if let Err(e) = self.socket.send_to(HEARTBEAT, (self.config.console_address.as_str(), self.config.heartbeat_port)).await {
+            debug!("Could not send GT7 heartbeat: {:?}", e);
+        }
+    }
+
+    fn apply(&mut self, raw: &DataGt7) {
+        let telemetry = &mut self.telemetry;
+        let on = |flag: u16| raw.flags & flag != 0;
+
+        telemetry.general = TelemetryGeneral {
+            gear: raw.gear(),
+            fuel: if raw.fuel_capacity > 0.0 { raw.fuel_level / raw.fuel_capacity } else { 0.0 },
+            fuel_capacity: if raw.fuel_capacity > 0.0 { Some(raw.fuel_capacity) } else { None },
+            speed: raw.speed,
+        };
+        telemetry.engine = TelemetryEngine {
+            rpm: raw.engine_rpm.max(0.0) as usize,
+            max_rpm: if raw.max_alert_rpm > 0 { Some(raw.max_alert_rpm as usize) } else { None },
+            idle_rpm: None,
+            shift_rpm: if raw.min_alert_rpm > 0 { Some(raw.min_alert_rpm as usize) } else { None },
+            turbo: if on(FLAG_HAS_TURBO) { Some(raw.boost - 1.0) } else { None },
+            engine_temp: Some(raw.water_temp),
+            oil_temp: Some(raw.oil_temp),
+            oil_pressure: Some(raw.oil_pressure),
+        };
+        telemetry.input = TelemetryInput {
+            throttle: raw.throttle as f32 / 255.0,
+            brake: raw.brake as f32 / 255.0,
+            clutch: raw.clutch,
+        };
+
+        let (min, max) = (raw.min_alert_rpm as f32, raw.max_alert_rpm as f32);
+        telemetry.lights = TelemetryLights {
+            available: DashLights::SHIFT | DashLights::HANDBRAKE | DashLights::SIDELIGHTS | DashLights::DIPPED | DashLights::FULL_BEAM | DashLights::TC,
+            active: [
+                (FLAG_REV_LIMITER_ALERT, DashLights::SHIFT),
+                (FLAG_HAND_BRAKE, DashLights::HANDBRAKE),
+                (FLAG_LIGHTS, DashLights::SIDELIGHTS),
+                (FLAG_LOW_BEAM, DashLights::DIPPED),
+                (FLAG_HIGH_BEAM, DashLights::FULL_BEAM),
+                (FLAG_TCS, DashLights::TC),
+            ].into_iter().filter(|(flag, _)| on(*flag)).map(|(_, light)| light).collect(),
+            rev_lights: if max > min { Some(((raw.engine_rpm - min) / (max - min)).clamp(0.0, 1.0)) } else { None },
+        };
+
+        // GT7 uses y up and z forward, we use z up and y forward.
+        // The rotation is a quaternion, its w component being the orientation relative to north.
+        let [qx, qy, qz] = raw.rotation;
+        let qw = raw.relative_orientation_to_north;
+        let [x, y, z] = raw.position;
+        let to_world = |[x, y, z]: [f32; 3]| [x, z, y];
+        telemetry.motion = TelemetryMotion {
+            heading: (2.0 * (qw * qy + qx * qz)).atan2(1.0 - 2.0 * (qx * qx + qy * qy)),
+            pitch: (2.0 * (qw * qx - qy * qz)).clamp(-1.0, 1.0).asin(),
+            roll: (2.0 * (qw * qz + qx * qy)).atan2(1.0 - 2.0 * (qx * qx + qz * qz)),
+            angular_velocity: to_world(raw.angular_velocity),
+            acceleration: [0.0; 3], // Not reported
+            velocity: to_world(raw.velocity),
+            position: [x as f64, z as f64, y as f64],
+        };
+
+        telemetry.wheels = (0..4).map(|i| TelemetryWheel {
+            suspension_travel: raw.suspension_height[i],
+            angular_velocity: -raw.wheel_rps[i],
+            speed: Some(-raw.wheel_rps[i] * raw.tyre_radius[i]),
+            tyre_temp: Some(raw.tyre_temp[i]),
+            ..Default::default()
+        }).collect();
+
+        let lap_time = |ms: i32| if ms > 0 { Some(ms as f32 / 1000.0) } else { None };
+        telemetry.timing.lap = if raw.current_lap > 0 { Some(raw.current_lap as u32) } else { None };
+        telemetry.timing.last_lap_time = lap_time(raw.last_lap_time_ms);
+        telemetry.timing.best_lap_time = lap_time(raw.best_lap_time_ms);
+        telemetry.timing.location = if on(FLAG_ON_TRACK) { CarLocation::Track } else { CarLocation::Unknown };
+        telemetry.session.total_laps = if raw.total_laps > 0 { Some(raw.total_laps as u32) } else { None };
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendGt7 {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 512];
+        loop {
+            if self.last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
+                self.send_heartbeat().await;
+            }
+
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            match DataGt7::parse(&buf[..n]) {
+                Ok(raw) if raw.flags & FLAG_LOADING != 0 => continue, // Values are meaningless while loading
+                Ok(raw) => {
+                    self.apply(&raw);
+                    return Some(self.telemetry.clone());
+                },
+                Err(e) => warn!("Dropping invalid GT7 packet: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::gt7::tests::encrypted_packet;
+    use tokio::net::UdpSocket;
+
+    /// A console that answers every heartbeat with the given packets
+    async fn stand_in_console(packets: Vec<Vec<u8>>) -> Gt7Config {
+        let console = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Gt7Config {
+            console_address: "127.0.0.1".to_string(),
+            heartbeat_port: console.local_addr().unwrap().port(),
+            listen: ListenConfig::any(0),
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 16];
+            while let Ok((n, client)) = console.recv_from(&mut buf).await {
+                assert_eq!(&buf[..n], HEARTBEAT);
+                for packet in &packets {
+                    console.send_to(packet, client).await.unwrap();
+                }
+            }
+        });
+        config
+    }
+
+    #[tokio::test]
+    async fn plays_back_encrypted_packets() {
+        let config = stand_in_console(vec![encrypted_packet(4000.0, 1), encrypted_packet(7150.0, 2)]).await;
+        let mut backend = BackendGt7::detect(config).await.expect("stand-in should be detected");
+        assert_eq!(backend.telemetry.engine.rpm, 4000);
+
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.game, "Gran Turismo 7");
+        assert_eq!(telemetry.engine.rpm, 7150);
+        assert_eq!(telemetry.engine.max_rpm, Some(7800));
+        assert_eq!(telemetry.engine.shift_rpm, Some(6500));
+        assert_eq!(telemetry.lights.rev_lights, Some(0.5));
+        assert!(telemetry.lights.is_on(DashLights::DIPPED));
+        assert!(!telemetry.lights.is_on(DashLights::SHIFT));
+        assert_eq!(telemetry.general.gear, 4);
+        assert_eq!(telemetry.general.fuel, 0.5);
+        assert_eq!(telemetry.general.fuel_capacity, Some(60.0));
+        assert_eq!(telemetry.engine.turbo.map(|t| (t * 10.0).round()), Some(8.0));
+        assert_eq!(telemetry.motion.position, [10.0, -30.0, 2.0]);
+        assert_eq!(telemetry.wheels[2].speed, Some(81.0 * 0.3));
+        assert_eq!(telemetry.timing.lap, Some(3));
+        assert_eq!(telemetry.timing.best_lap_time, Some(95.123));
+        assert_eq!(telemetry.timing.last_lap_time, None);
+        assert_eq!(telemetry.session.total_laps, Some(5));
+    }
+
+    #[tokio::test]
+    async fn ignores_other_packets() {
+        let mut not_gt7 = encrypted_packet(4000.0, 1);
+        not_gt7[0x40] ^= 0xff;
+        let config = stand_in_console(vec![not_gt7]).await;
+        assert!(BackendGt7::detect(config).await.is_none());
+    }
+}
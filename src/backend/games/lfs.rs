@@ -0,0 +1,112 @@
+// Live for Speed sends OutGauge and OutSim to the ports set in its cfg.txt (`OutGauge Port`, `OutSim Port`).
+// Both can point at the same port, packets are told apart by trying each decoder in turn.
+// For wheel data, `OutSim Opts` must include OSO_WHEELS and match `LfsConfig::outsim_opts`.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::{outgauge::DataOutGauge, outsim::{self, DataOutSim}};
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LfsConfig {
+    pub outgauge: ListenConfig,
+    pub outsim: ListenConfig,
+    pub outsim_opts: u16,   // Must match `OutSim Opts` in LFS, 0 for the legacy packet
+}
+
+impl Default for LfsConfig {
+    fn default() -> Self {
+        Self {
+            outgauge: ListenConfig::local(30000),
+            outsim: ListenConfig::local(30001),
+            outsim_opts: outsim::OSO_HEADER | outsim::OSO_ID | outsim::OSO_TIME | outsim::OSO_MAIN | outsim::OSO_INPUTS | outsim::OSO_DRIVE | outsim::OSO_DISTANCE | outsim::OSO_WHEELS, // "OutSim Opts ff"
+        }
+    }
+}
+
+pub struct BackendLfs {
+    outgauge_socket: TelemetrySocket,
+    outsim_socket: Option<TelemetrySocket>, // None if OutSim shares the OutGauge port
+    config: LfsConfig,
+
+    telemetry: Telemetry,
+}
+
+impl BackendLfs {
+    pub async fn new(config: LfsConfig) -> Option<Self> {
+        let outgauge_socket = match config.outgauge.bind().await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                return None;
+            },
+            Ok(socket) => socket,
+        };
+        let outsim_socket = if config.outsim.port != config.outgauge.port {
+            match config.outsim.bind().await {
+                Err(e) => {
+                    error!("Error: {:?}", e);
+                    return None;
+                },
+                Ok(socket) => Some(socket),
+            }
+        } else {
+            None
+        };
+        Some(Self {
+            outgauge_socket,
+            outsim_socket,
+            config,
+
+            telemetry: Telemetry {
+                game: "Live for Speed",
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Merges a packet into the latest telemetry. Returns false if it was neither OutGauge nor OutSim.
+    /// Packets from the dedicated OutSim socket are never tried as OutGauge.
+    fn handle_packet(&mut self, buf: &[u8], maybe_outgauge: bool) -> bool {
+        if maybe_outgauge {
+            match DataOutGauge::parse(buf) {
+                Ok(raw) => {
+                    super::apply_outgauge(&mut self.telemetry, &raw);
+                    return true;
+                },
+                Err(e) => trace!("Not an outgauge packet: {e}"),
+            }
+        }
+        match DataOutSim::parse(buf, self.config.outsim_opts) {
+            Ok(raw) => {
+                super::apply_outsim(&mut self.telemetry, &raw);
+                true
+            },
+            Err(e) => {
+                warn!("Dropping invalid packet, neither outgauge nor outsim ({e})");
+                false
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendLfs {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut outgauge_buf = [0u8; 512];
+        let mut outsim_buf = [0u8; 512];
+        loop {
+            let (result, buf, maybe_outgauge) = tokio::select! {
+                result = self.outgauge_socket.recv(&mut outgauge_buf) => (result, &outgauge_buf, true),
+                result = super::recv_optional(&self.outsim_socket, &mut outsim_buf) => (result, &outsim_buf, false),
+            };
+            let n = result.ok()?;
+            if self.handle_packet(&buf[..n], maybe_outgauge) {
+                return Some(self.telemetry.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// Where a backend listens for telemetry. Most games send to localhost by default, but a game running on another PC
+// needs the backend to listen on the LAN, and some rigs send telemetry to a multicast group so several tools can
+// read it at once. Addresses are kept as text so they can be edited as-is, empty meaning the option is off.
+// Only one program can bind a port, so every packet received can also be relayed unchanged to other tools (a bass
+// shaker app, a second dashboard), optionally at a lower rate than the game sends.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+use tokio::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenConfig {
+    pub bind_address: String,       // 127.0.0.1 for games on this PC, 0.0.0.0 for games anywhere on the network
+    pub port: u16,
+    pub source_filter: String,      // Only accept packets sent from this address, empty to accept any
+    pub multicast_group: String,    // IPv4 group to join, empty to not join one
+    // Comma separated host:port list to relay every packet to, empty to not relay. Relaying to this port itself is
+    // refused, but only when the target is a loopback address or the bind address: with 0.0.0.0 the PC's own LAN
+    // address isn't recognised and would still relay in a loop.
+    pub forward_to: String,
+    pub forward_rate_hz: f32,       // Most packets per second to relay, 0 to relay all of them
+}
+
+impl Default for ListenConfig {
+    fn default() -> Self {
+        Self::local(0)
+    }
+}
+
+impl ListenConfig {
+    /// Only hears games on this PC
+    pub fn local(port: u16) -> Self {
+        Self {
+            bind_address: "127.0.0.1".to_string(),
+            port,
+            source_filter: String::new(),
+            multicast_group: String::new(),
+            forward_to: String::new(),
+            forward_rate_hz: 0.0,
+        }
+    }
+
+    /// Hears games anywhere on the network, for games that broadcast their telemetry
+    pub fn any(port: u16) -> Self {
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            ..Self::local(port)
+        }
+    }
+
+    pub async fn bind(&self) -> std::io::Result<TelemetrySocket> {
+        let invalid = |what: &str, address: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid {what}: {address:?}"));
+        let bind_address: IpAddr = self.bind_address.trim().parse().map_err(|_| invalid("bind address", &self.bind_address))?;
+        let source_filter = match self.source_filter.trim() {
+            "" => None,
+            filter => Some(filter.parse::<IpAddr>().map_err(|_| invalid("source filter", filter))?),
+        };
+        let multicast_group = match self.multicast_group.trim() {
+            "" => None,
+            group => Some(group.parse::<Ipv4Addr>().ok().filter(Ipv4Addr::is_multicast).ok_or_else(|| invalid("multicast group", group))?),
+        };
+
+        let socket = match multicast_group {
+            // Multicast packets are addressed to the group, so a socket bound to a single address never sees them.
+            // The bind address picks the interface to join on instead.
+            Some(group) => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, self.port)).await?;
+                let interface = match bind_address {
+                    IpAddr::V4(address) if !address.is_loopback() => address,
+                    _ => Ipv4Addr::UNSPECIFIED,
+                };
+                socket.join_multicast_v4(group, interface)?;
+                socket
+            },
+            None => UdpSocket::bind((bind_address, self.port)).await?,
+        };
+
+        // A socket can only send to addresses of its own family, and a multicast socket is always IPv4
+        let ipv4 = multicast_group.is_some() || bind_address.is_ipv4();
+        let mut targets = Vec::new();
+        for target in self.forward_to.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let address = tokio::net::lookup_host(target).await.map_err(|_| invalid("forward target", target))?
+                .find(|address| address.is_ipv4() == ipv4)
+                .ok_or_else(|| invalid("forward target", target))?;
+            // Relaying to ourselves would send every packet around in a loop
+            if address.port() == self.port && (address.ip().is_loopback() || address.ip() == bind_address) {
+                return Err(invalid("forward target", target));
+            }
+            targets.push(address);
+        }
+        let forward = match targets.is_empty() {
+            true => None,
+            false => Some(Forward {
+                targets,
+                min_interval: (self.forward_rate_hz > 0.0).then(|| Duration::from_secs_f32(1.0 / self.forward_rate_hz)),
+                last: Mutex::new(None),
+            }),
+        };
+        Ok(TelemetrySocket { socket, source_filter, forward })
+    }
+}
+
+/// Where received packets are relayed to, and when that last happened
+struct Forward {
+    targets: Vec<SocketAddr>,
+    min_interval: Option<Duration>,
+    last: Mutex<Option<Instant>>,
+}
+
+impl Forward {
+    fn relay(&self, socket: &UdpSocket, buf: &[u8]) {
+        if let Some(min_interval) = self.min_interval {
+            let mut last = self.last.lock().unwrap();
+            if last.is_some_and(|last| last.elapsed() < min_interval) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        for target in &self.targets {
+            // Never wait on other tools, a packet they miss is replaced by the next one anyway
+            if let Err(e) = socket.try_send_to(buf, *target) {
+                trace!("Could not relay packet to {target}: {:?}", e);
+            }
+        }
+    }
+}
+
+/// A bound telemetry socket that drops packets from senders other than the configured source,
+/// and relays the packets it keeps to the configured forward targets
+pub struct TelemetrySocket {
+    socket: UdpSocket,
+    source_filter: Option<IpAddr>,
+    forward: Option<Forward>,
+}
+
+impl TelemetrySocket {
+    pub async fn recv(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.recv_from(buf).await.map(|(n, _)| n)
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        loop {
+            let (n, from) = self.socket.recv_from(buf).await?;
+            match self.source_filter {
+                Some(source) if source != from.ip() => trace!("Ignoring packet from {from}"),
+                _ => {
+                    if let Some(forward) = &self.forward {
+                        forward.relay(&self.socket, &buf[..n]);
+                    }
+                    return Ok((n, from));
+                },
+            }
+        }
+    }
+
+    pub async fn send_to(&self, buf: &[u8], target: (&str, u16)) -> std::io::Result<usize> {
+        self.socket.send_to(buf, target).await
+    }
+
+    /// Lets tests bind port 0 and find out which port they got
+    #[cfg(test)]
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn set_broadcast(&self, on: bool) -> std::io::Result<()> {
+        self.socket.set_broadcast(on)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn source_filter() {
+        let listen = ListenConfig {
+            source_filter: "127.0.0.2".to_string(),
+            ..ListenConfig::local(0)
+        };
+        let socket = listen.bind().await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+
+        let other = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let game = UdpSocket::bind("127.0.0.2:0").await.unwrap();
+        other.send_to(b"other", ("127.0.0.1", port)).await.unwrap();
+        game.send_to(b"game", ("127.0.0.1", port)).await.unwrap();
+
+        let mut buf = [0u8; 16];
+        let (n, from) = socket.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"game");
+        assert_eq!(from.ip(), "127.0.0.2".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn invalid_addresses() {
+        let bind = |listen: ListenConfig| async move { listen.bind().await.map(|_| ()).map_err(|e| e.kind()) };
+        assert_eq!(bind(ListenConfig { bind_address: "localhost".to_string(), ..Default::default() }).await, Err(std::io::ErrorKind::InvalidInput));
+        assert_eq!(bind(ListenConfig { source_filter: "nope".to_string(), ..Default::default() }).await, Err(std::io::ErrorKind::InvalidInput));
+        // Not a multicast address
+        assert_eq!(bind(ListenConfig { multicast_group: "192.168.1.1".to_string(), ..Default::default() }).await, Err(std::io::ErrorKind::InvalidInput));
+        assert_eq!(bind(ListenConfig { bind_address: " 127.0.0.1 ".to_string(), ..Default::default() }).await, Ok(()));
+        assert_eq!(bind(ListenConfig { forward_to: "127.0.0.1".to_string(), ..Default::default() }).await, Err(std::io::ErrorKind::InvalidInput));
+        // Relaying to the listening port itself
+        assert_eq!(bind(ListenConfig { forward_to: "127.0.0.1:4444".to_string(), ..ListenConfig::local(4444) }).await, Err(std::io::ErrorKind::InvalidInput));
+        // No IPv4 address to send to from an IPv4 socket
+        assert_eq!(bind(ListenConfig { forward_to: "[::1]:4445".to_string(), ..Default::default() }).await, Err(std::io::ErrorKind::InvalidInput));
+    }
+
+    #[tokio::test]
+    async fn forwards_packets() {
+        let first = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let listen = ListenConfig {
+            // localhost may resolve to ::1 first, which this IPv4 socket can't send to
+            forward_to: format!("{}, localhost:{}", first.local_addr().unwrap(), second.local_addr().unwrap().port()),
+            forward_rate_hz: 1.0,
+            ..ListenConfig::local(0)
+        };
+        let socket = listen.bind().await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+
+        let game = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut buf = [0u8; 16];
+        for packet in [b"one", b"two"] {
+            game.send_to(packet, ("127.0.0.1", port)).await.unwrap();
+            let n = socket.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], packet);
+        }
+
+        // Only the first packet fits into the rate limit
+        for target in [&first, &second] {
+            let n = target.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"one");
+        }
+        assert!(tokio::time::timeout(Duration::from_millis(50), first.recv(&mut buf)).await.is_err());
+    }
+}
@@ -1,45 +1,275 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+
 use async_trait::async_trait;
-use crate::telemetry::Telemetry;
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+use crate::telemetry::*;
+use detection::{DetectionConfig, DetectionRule, ProcessList, SniffRule, sniff};
+use listen::ListenConfig;
+use crate::backend::protocols::{outgauge::DataOutGauge, outsim::DataOutSim};
 
 #[async_trait]
 pub trait GameBackend {
     async fn next_event(&mut self) -> Option<Telemetry>;
 }
 
-pub fn find_running_supported_games() -> Vec<String> {
-    use sysinfo::{ProcessExt, System, SystemExt};
+/// Settings for every game backend, and for finding the game to connect to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamesConfig {
+    pub detection: DetectionConfig,
+    pub beamng: beamng::BeamNGConfig,
+    pub lfs: lfs::LfsConfig,
+    pub forza: forza::ForzaConfig,
+    pub f1: f1::F1Config,
+    pub assetto_corsa: assetto_corsa::AssettoCorsaConfig,
+    pub acc: acc::AccConfig,
+    pub sms: sms::SmsConfig,
+    pub dirt_rally_2: rally::RallyConfig,
+    pub ea_wrc: rally::RallyConfig,
+    pub gt7: gt7::Gt7Config,
+    pub ets2: ets2::Ets2Config,
+    pub rbr: rbr::RbrConfig,
+}
 
-    let mut sys = System::new_all();
-    sys.refresh_all();
+impl Default for GamesConfig {
+    fn default() -> Self {
+        Self {
+            detection: DetectionConfig::default(),
+            beamng: Default::default(),
+            lfs: Default::default(),
+            forza: Default::default(),
+            f1: Default::default(),
+            assetto_corsa: Default::default(),
+            acc: Default::default(),
+            sms: Default::default(),
+            dirt_rally_2: rally::RallyConfig::default(),
+            ea_wrc: rally::RallyConfig::ea_wrc(),
+            gt7: Default::default(),
+            ets2: Default::default(),
+            rbr: Default::default(),
+        }
+    }
+}
 
-    let mut process_names = Vec::new();
-    for (_pid, process) in sys.processes() {
-        let name = process.name().replace(".exe", "").to_string();
-        process_names.push(name);
+impl GamesConfig {
+    /// Every port the backends listen on, for editing them side by side
+    pub fn listeners_mut(&mut self) -> Vec<(&'static str, &mut ListenConfig)> {
+        vec![
+            ("BeamNG.drive OutGauge", &mut self.beamng.outgauge),
+            ("BeamNG.drive OutSim", &mut self.beamng.outsim),
+            ("Live for Speed OutGauge", &mut self.lfs.outgauge),
+            ("Live for Speed OutSim", &mut self.lfs.outsim),
+            ("Forza", &mut self.forza.listen),
+            ("F1", &mut self.f1.listen),
+            ("Project CARS 2 / Automobilista 2", &mut self.sms.listen),
+            ("DiRT Rally 2.0", &mut self.dirt_rally_2.listen),
+            ("EA Sports WRC", &mut self.ea_wrc.listen),
+            ("Gran Turismo 7", &mut self.gt7.listen),
+            ("Richard Burns Rally", &mut self.rbr.listen),
+        ]
     }
+}
+
+/// Starts the backend for one of the internal game keys, None if it can't start
+async fn connect(game: &str, config: &GamesConfig) -> Option<Box<dyn GameBackend + Send>> {
+    let backend: Box<dyn GameBackend + Send> = match game {
+        "beamng" => Box::new(beamng::BackendBeamNG::new(config.beamng.clone()).await?),
+        "lfs" => Box::new(lfs::BackendLfs::new(config.lfs.clone()).await?),
+        "forza" => Box::new(forza::BackendForza::new(config.forza.clone()).await?),
+        "f1" => Box::new(f1::BackendF1::detect(config.f1.clone()).await?),
+        "assetto_corsa" => Box::new(assetto_corsa::BackendAssettoCorsa::new(config.assetto_corsa.clone()).await?),
+        "acc" => Box::new(acc::BackendAcc::new(config.acc.clone()).await?),
+        "pcars2" => Box::new(sms::BackendSms::new(config.sms.clone(), "Project CARS 2").await?),
+        "ams2" => Box::new(sms::BackendSms::new(config.sms.clone(), "Automobilista 2").await?),
+        // Both games send the same packets, so when they are told apart by their telemetry the name stays generic
+        "sms" => Box::new(sms::BackendSms::new(config.sms.clone(), "Project CARS 2 / Automobilista 2").await?),
+        "dirt_rally_2" => Box::new(rally::BackendRally::new(config.dirt_rally_2.clone()).await?),
+        "ea_wrc" => Box::new(rally::BackendRally::new(config.ea_wrc.clone()).await?),
+        "ets2" | "ats" => Box::new(ets2::BackendEts2::new(config.ets2.clone()).await?),
+        "rbr" => Box::new(rbr::BackendRbr::new(config.rbr.clone()).await?),
+        _ => {
+            warn!("Found {game}, but there is no backend for it");
+            return None;
+        },
+    };
+    Some(backend)
+}
 
-    // List of supported games and internal name
-    let mut supported_games: HashMap<&'static str, &'static str> = HashMap::new();
-    supported_games.insert("BeamNG.drive.x64", "beamng");
+/// The games that send telemetry without being asked, and how to recognise their packets
+fn sniff_rules(config: &GamesConfig) -> Vec<SniffRule> {
+    use crate::backend::protocols::{dirt_rally::DataDirtRally, ea_wrc::PacketStructure, f1::PacketHeader, forza::DataForza, rbr::DataRbr, sms::DataSms};
 
-    process_names.into_iter().filter_map(|name| supported_games.get(&name.as_str()).map(|s| s.to_string())).collect()
+    let mut rules = vec![
+        SniffRule::new("beamng", &config.beamng.outgauge, |buf| DataOutGauge::parse(buf).is_ok()),
+        SniffRule::new("lfs", &config.lfs.outgauge, |buf| DataOutGauge::parse(buf).is_ok()),
+        SniffRule::new("forza", &config.forza.listen, |buf| DataForza::parse(buf).is_ok()),
+        SniffRule::new("sms", &config.sms.listen, |buf| DataSms::parse(buf).is_ok()),
+        SniffRule::new("rbr", &config.rbr.listen, |buf| DataRbr::parse(buf).is_ok()),
+        // The Codemasters games share a port, F1 has a versioned header and DiRT Rally a fixed size
+        SniffRule::new("f1", &config.f1.listen, |buf| PacketHeader::parse(buf).is_ok()),
+        SniffRule::new("dirt_rally_2", &config.dirt_rally_2.listen, |buf| DataDirtRally::parse(buf).is_ok()),
+    ];
+    // EA WRC packets can only be recognised with the structure the game was told to send
+    if let rally::RallyLayout::EaWrc { structure_file, channels_file } = &config.ea_wrc.layout {
+        if let Ok(structure) = PacketStructure::load(structure_file, channels_file) {
+            rules.push(SniffRule::new("ea_wrc", &config.ea_wrc.listen, move |buf| structure.parse(buf).is_ok()));
+        }
+    }
+    rules
+}
+
+/// A game that can be found by its executable, and how it was found if it's running right now
+pub struct GameStatus {
+    pub game: String,
+    pub executables: Vec<String>,
+    pub running: Option<DetectionRule>,
+}
+
+/// Looks at the process list once for every game in the executable table
+pub fn game_status(config: &GamesConfig) -> Vec<GameStatus> {
+    let table = &config.detection.executables;
+    let running = ProcessList::default().find_running_supported_games(table, Duration::ZERO);
+    table.games.iter().map(|(game, executables)| GameStatus {
+        game: game.clone(),
+        executables: executables.clone(),
+        running: running.iter().find(|d| &d.game == game).map(|d| d.rule.clone()),
+    }).collect()
+}
+
+/// Looks for a game to connect to, by listening for its telemetry first
+pub struct Detector {
+    config: GamesConfig,
+    sniff_rules: Vec<SniffRule>,
+    unbindable: HashSet<u16>,       // Ports already warned about, a new config gets a new detector and new warnings
+    processes: ProcessList,
 }
 
-pub async fn find_next_backend() -> Option<Box<dyn GameBackend + Send>> {
-    for s in find_running_supported_games() {
-        match s.as_str() {
-            "beamng" => {
-                if let Some(b) = beamng::BackendBeamNG::new().await {
-                    info!("Backend connected: {s}!");
-                    return Some(Box::new(b) as Box<dyn GameBackend + Send>);
+impl Detector {
+    pub fn new(config: GamesConfig) -> Self {
+        Self {
+            sniff_rules: sniff_rules(&config),
+            unbindable: HashSet::new(),
+            config,
+            processes: ProcessList::default(),
+        }
+    }
+
+    pub async fn find_next_backend(&mut self) -> Option<Box<dyn GameBackend + Send>> {
+        let detection = &self.config.detection;
+        if let Some(detected) = sniff(&self.sniff_rules, Duration::from_millis(detection.sniff_window_ms), &mut self.unbindable).await {
+            if let Some(b) = connect(&detected.game, &self.config).await {
+                info!("Backend connected: {}, found by {}!", detected.game, detected.rule);
+                return Some(b);
+            }
+        }
+
+        // GT7 only sends telemetry after a heartbeat, so it has to be asked
+        if let Some(b) = gt7::BackendGt7::detect(self.config.gt7.clone()).await {
+            info!("Backend connected: gt7!");
+            return Some(Box::new(b) as Box<dyn GameBackend + Send>);
+        }
+
+        if detection.process_fallback {
+            let interval = Duration::from_millis(detection.process_interval_ms);
+            for detected in self.processes.find_running_supported_games(&detection.executables, interval) {
+                if let Some(b) = connect(&detected.game, &self.config).await {
+                    info!("Backend connected: {}, found by {}!", detected.game, detected.rule);
+                    return Some(b);
                 }
-            },
-            _ => unreachable!(),
+            }
         }
+        None
+    }
+}
+
+/// Receives from a socket that may not be bound. Never resolves if it isn't, so it can sit in a `select!`.
+async fn recv_optional(socket: &Option<listen::TelemetrySocket>, buf: &mut [u8]) -> std::io::Result<usize> {
+    match socket {
+        Some(socket) => socket.recv(buf).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Fills in everything an OutGauge packet covers.
+/// Games that leave some of these fields hardcoded should clear them again afterwards.
+fn apply_outgauge(telemetry: &mut Telemetry, raw: &DataOutGauge) {
+    telemetry.general = TelemetryGeneral {
+        gear: raw.gear(),
+        fuel: raw.fuel,
+        fuel_capacity: None,
+        speed: raw.speed,
+    };
+    telemetry.engine = TelemetryEngine {
+        rpm: raw.rpm as usize,
+        max_rpm: None,
+        idle_rpm: None,
+        shift_rpm: None,
+        turbo: raw.turbo(),
+        engine_temp: Some(raw.engine_temp),
+        oil_temp: Some(raw.oil_temp),
+        oil_pressure: Some(raw.oil_pressure),
+    };
+    telemetry.input = TelemetryInput {
+        throttle: raw.throttle,
+        brake: raw.brake,
+        clutch: raw.clutch,
+    };
+    telemetry.lights = TelemetryLights {
+        available: DashLights::from_bits_truncate(raw.dash_lights),
+        active: DashLights::from_bits_truncate(raw.show_lights),
+        rev_lights: None,
+    };
+    telemetry.units = TelemetryUnits {
+        prefers_miles: !raw.prefers_km(),
+        prefers_psi: !raw.prefers_bar(),
+    };
+}
+
+/// Fills in everything an OutSim packet covers, depending on which blocks it contains.
+fn apply_outsim(telemetry: &mut Telemetry, raw: &DataOutSim) {
+    if let Some(main) = &raw.main {
+        telemetry.motion = TelemetryMotion {
+            heading: main.heading,
+            pitch: main.pitch,
+            roll: main.roll,
+            angular_velocity: main.ang_vel,
+            acceleration: main.accel,
+            velocity: main.vel,
+            position: main.position(),
+        };
+    }
+    if let Some(wheels) = &raw.wheels {
+        // OutSim orders the wheels rear left, rear right, front left, front right
+        telemetry.wheels = [2, 3, 0, 1].iter().map(|i| {
+            let wheel = &wheels[*i];
+            TelemetryWheel {
+                suspension_travel: wheel.susp_deflect,
+                angular_velocity: wheel.ang_vel,
+                speed: None,
+                vertical_load: wheel.vertical_load,
+                slip_ratio: wheel.slip_ratio,
+                slip_angle: wheel.tangent_slip_angle.atan(),
+                tyre_temp: Some(wheel.air_temp as f32),
+                tyre_pressure: None,
+                brake_temp: None,
+            }
+        }).collect();
     }
-    None
 }
 
+pub mod detection;
+pub mod listen;
+
 // Importing each supported game
 pub mod beamng;
+pub mod lfs;
+pub mod f1;
+pub mod forza;
+pub mod assetto_corsa;
+pub mod acc;
+pub mod sms;
+pub mod rally;
+pub mod gt7;
+pub mod ets2;
+pub mod rbr;
@@ -0,0 +1,318 @@
+// Codemasters' rally games. DiRT Rally 2.0 sends its fixed "extradata" layout once UDP telemetry is enabled in
+// `hardware_settings_config.xml`, EA Sports WRC sends whatever its telemetry config asks for, so it needs the packet
+// structure file the game was told to use. Stage progress is reported as the lap distance and the track (stage) length.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::{dirt_rally::{self, DataDirtRally}, ea_wrc::{self, PacketStructure, WrcPacket}};
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RallyLayout {
+    DirtRally2,
+    EaWrc {
+        structure_file: PathBuf,
+        channels_file: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RallyConfig {
+    pub listen: ListenConfig,
+    pub layout: RallyLayout,
+}
+
+impl Default for RallyConfig {
+    fn default() -> Self {
+        Self {
+            listen: ListenConfig::local(dirt_rally::DEFAULT_PORT),
+            layout: RallyLayout::DirtRally2,
+        }
+    }
+}
+
+impl RallyConfig {
+    /// EA WRC with the structure and channel files where the game keeps them
+    pub fn ea_wrc() -> Self {
+        let home = std::env::var_os("USERPROFILE").or_else(|| std::env::var_os("HOME")).map(PathBuf::from).unwrap_or_default();
+        let telemetry_dir = home.join("Documents").join("My Games").join("WRC").join("telemetry");
+        Self {
+            listen: ListenConfig::local(ea_wrc::DEFAULT_PORT),
+            layout: RallyLayout::EaWrc {
+                structure_file: telemetry_dir.join("udp").join("wrc.json"),
+                channels_file: telemetry_dir.join("readme").join("channels.json"),
+            },
+        }
+    }
+}
+
+enum Decoder {
+    DirtRally2,
+    EaWrc(PacketStructure),
+}
+
+pub struct BackendRally {
+    socket: TelemetrySocket,
+    decoder: Decoder,
+
+    telemetry: Telemetry,
+}
+
+impl BackendRally {
+    pub async fn new(config: RallyConfig) -> Option<Self> {
+        let (decoder, game) = match &config.layout {
+            RallyLayout::DirtRally2 => (Decoder::DirtRally2, "DiRT Rally 2.0"),
+            RallyLayout::EaWrc { structure_file, channels_file } => match PacketStructure::load(structure_file, channels_file) {
+                Ok(structure) => (Decoder::EaWrc(structure), "EA Sports WRC"),
+                Err(e) => {
+                    error!("Could not load the EA WRC packet structure: {e}");
+                    return None;
+                },
+            },
+        };
+
+        match config.listen.bind().await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            },
+            Ok(socket) => {
+                Some(Self {
+                    socket,
+                    decoder,
+
+                    telemetry: Telemetry {
+                        game,
+                        ..Default::default()
+                    },
+                })
+            }
+        }
+    }
+
+    fn apply_dirt_rally(&mut self, raw: &DataDirtRally) {
+        let telemetry = &mut self.telemetry;
+
+        telemetry.general = TelemetryGeneral {
+            gear: raw.gear(),
+            fuel: if raw.fuel_capacity > 0.0 { raw.fuel_in_tank / raw.fuel_capacity } else { 0.0 },
+            fuel_capacity: if raw.fuel_capacity > 0.0 { Some(raw.fuel_capacity) } else { None },
+            speed: raw.speed,
+        };
+        telemetry.engine.rpm = (raw.engine_rate * 10.0) as usize;
+        telemetry.engine.max_rpm = if raw.max_rpm > 0.0 { Some((raw.max_rpm * 10.0) as usize) } else { None };
+        telemetry.engine.idle_rpm = if raw.idle_rpm > 0.0 { Some((raw.idle_rpm * 10.0) as usize) } else { None };
+        telemetry.input = TelemetryInput {
+            throttle: raw.throttle,
+            brake: raw.brake,
+            clutch: raw.clutch,
+        };
+
+        let (heading, pitch, roll) = orientation(to_world(raw.pitch_vector), to_world(raw.roll_vector));
+        let (sin, cos) = heading.sin_cos();
+        let [lateral, longitudinal] = [raw.g_force_lateral * 9.81, raw.g_force_longitudinal * 9.81];
+        let [x, y, z] = to_world(raw.position);
+        telemetry.motion = TelemetryMotion {
+            heading,
+            pitch,
+            roll,
+            angular_velocity: [0.0; 3], // Not reported
+            acceleration: [lateral * cos - longitudinal * sin, lateral * sin + longitudinal * cos, 0.0],
+            velocity: to_world(raw.velocity),
+            position: [x as f64, y as f64, z as f64],
+        };
+
+        // DiRT orders the wheels rear left, rear right, front left, front right
+        telemetry.wheels = [2, 3, 0, 1].iter().map(|i| TelemetryWheel {
+            suspension_travel: raw.suspension_position[*i] / 1000.0,
+            angular_velocity: 0.0, // Only the surface speed is reported
+            speed: Some(raw.wheel_speed[*i]),
+            brake_temp: Some(raw.brake_temp[*i]),
+            tyre_pressure: Some(raw.tyre_pressure[*i] / 14.5038),
+            ..Default::default()
+        }).collect();
+
+        telemetry.timing = TelemetryTiming {
+            lap: Some(raw.lap as u32 + 1),
+            position: if raw.car_position > 0.0 { Some(raw.car_position as u32) } else { None },
+            current_lap_time: Some(raw.lap_time),
+            last_lap_time: if raw.last_lap_time > 0.0 { Some(raw.last_lap_time) } else { None },
+            lap_distance: Some(raw.lap_distance),
+            ..Default::default()
+        };
+        telemetry.session.total_laps = if raw.total_laps > 0.0 { Some(raw.total_laps as u32) } else { None };
+        telemetry.session.track_length = if raw.track_length > 0.0 { Some(raw.track_length) } else { None };
+    }
+
+    /// Only touches the fields whose channels are in the packet, the structure may leave any of them out.
+    fn apply_ea_wrc(&mut self, packet: &WrcPacket) {
+        let telemetry = &mut self.telemetry;
+        let get = |channel: &str| packet.get(channel).map(|v| v as f32);
+        let get_vector = |name: &str| Some(to_world([get(&format!("{name}_x"))?, get(&format!("{name}_y"))?, get(&format!("{name}_z"))?]));
+
+        if let Some(gear) = get("vehicle_gear_index") {
+            telemetry.general.gear = if Some(gear) == get("vehicle_gear_index_neutral") {
+                0
+            } else if Some(gear) == get("vehicle_gear_index_reverse") {
+                -1
+            } else {
+                gear as isize
+            };
+        }
+        if let Some(speed) = get("vehicle_speed") {
+            telemetry.general.speed = speed;
+        }
+        if let Some(rpm) = get("vehicle_engine_rpm_current") {
+            telemetry.engine.rpm = rpm.max(0.0) as usize;
+        }
+        if let Some(max_rpm) = get("vehicle_engine_rpm_max") {
+            telemetry.engine.max_rpm = Some(max_rpm as usize);
+        }
+        if let Some(idle_rpm) = get("vehicle_engine_rpm_idle") {
+            telemetry.engine.idle_rpm = Some(idle_rpm as usize);
+        }
+        if let Some(throttle) = get("vehicle_throttle") {
+            telemetry.input.throttle = throttle;
+        }
+        if let Some(brake) = get("vehicle_brake") {
+            telemetry.input.brake = brake;
+        }
+        if let Some(clutch) = get("vehicle_clutch") {
+            telemetry.input.clutch = clutch;
+        }
+        if let Some(shift_lights) = get("shiftlights_fraction") {
+            telemetry.lights.rev_lights = Some(shift_lights);
+        }
+
+        if let (Some(forward), Some(left)) = (get_vector("vehicle_forward_direction"), get_vector("vehicle_left_direction")) {
+            let (heading, pitch, roll) = orientation(forward, left.map(|v| -v));
+            telemetry.motion.heading = heading;
+            telemetry.motion.pitch = pitch;
+            telemetry.motion.roll = roll;
+        }
+        if let Some(acceleration) = get_vector("vehicle_acceleration") {
+            telemetry.motion.acceleration = acceleration;
+        }
+        if let Some(velocity) = get_vector("vehicle_velocity") {
+            telemetry.motion.velocity = velocity;
+        }
+        if let Some(position) = get_vector("vehicle_position") {
+            telemetry.motion.position = position.map(|v| v as f64);
+        }
+
+        let wheels = ["fl", "fr", "bl", "br"];
+        if wheels.iter().any(|wheel| get(&format!("vehicle_hub_position_{wheel}")).is_some()) {
+            telemetry.wheels = wheels.iter().map(|wheel| TelemetryWheel {
+                suspension_travel: get(&format!("vehicle_hub_position_{wheel}")).unwrap_or_default(),
+                speed: get(&format!("vehicle_cp_forward_speed_{wheel}")),
+                brake_temp: get(&format!("vehicle_brake_temperature_{wheel}")),
+                ..Default::default()
+            }).collect();
+        }
+
+        if let Some(distance) = get("stage_current_distance") {
+            telemetry.timing.lap_distance = Some(distance);
+        }
+        if let Some(time) = get("stage_current_time") {
+            telemetry.timing.current_lap_time = Some(time);
+        }
+        if let Some(length) = get("stage_length") {
+            telemetry.session.track_length = Some(length);
+        }
+    }
+}
+
+/// Codemasters uses y up and z forward, we use z up and y forward
+fn to_world([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, z, y]
+}
+
+/// Heading, pitch and roll from the car's forward and right pointing unit vectors
+fn orientation(forward: [f32; 3], right: [f32; 3]) -> (f32, f32, f32) {
+    let heading = (-forward[0]).atan2(forward[1]);
+    let pitch = forward[2].clamp(-1.0, 1.0).asin();
+    let roll = right[2].clamp(-1.0, 1.0).asin();
+    (heading, pitch, roll)
+}
+
+#[async_trait]
+impl super::GameBackend for BackendRally {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 2048];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            match &self.decoder {
+                Decoder::DirtRally2 => match DataDirtRally::parse(&buf[..n]) {
+                    Ok(raw) => self.apply_dirt_rally(&raw),
+                    Err(e) => {
+                        warn!("Dropping invalid DiRT Rally packet: {e}");
+                        continue;
+                    },
+                },
+                Decoder::EaWrc(structure) => match structure.parse(&buf[..n]) {
+                    Ok(packet) => self.apply_ea_wrc(&packet),
+                    Err(e) => {
+                        warn!("Dropping invalid EA WRC packet: {e}");
+                        continue;
+                    },
+                },
+            }
+            return Some(self.telemetry.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::ea_wrc::tests::*;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn ea_wrc_from_structure_file() {
+        let dir = std::env::temp_dir().join(format!("dysoon_simhub_wrc_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("structure.json"), STRUCTURE).unwrap();
+        std::fs::write(dir.join("channels.json"), CHANNELS).unwrap();
+
+        let mut backend = BackendRally::new(RallyConfig {
+            listen: ListenConfig::local(0),
+            layout: RallyLayout::EaWrc {
+                structure_file: dir.join("structure.json"),
+                channels_file: dir.join("channels.json"),
+            },
+        }).await.expect("structure should load");
+        std::fs::remove_dir_all(&dir).unwrap();
+        let port = backend.socket.local_addr().unwrap().port();
+
+        let game = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        game.send_to(&session_update(7, 6200.0, 2200.0), ("127.0.0.1", port)).await.unwrap();
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.game, "EA Sports WRC");
+        assert_eq!(telemetry.general.gear, -1);
+        assert_eq!(telemetry.general.speed, 25.0);
+        assert_eq!(telemetry.engine.rpm, 6200);
+        assert_eq!(telemetry.engine.max_rpm, Some(7500));
+        assert_eq!(telemetry.timing.lap_distance, Some(2200.0));
+        assert_eq!(telemetry.session.track_length, Some(8800.0));
+    }
+
+    #[tokio::test]
+    async fn missing_structure_file() {
+        let config = RallyConfig {
+            layout: RallyLayout::EaWrc {
+                structure_file: PathBuf::from("/nonexistent/wrc.json"),
+                channels_file: PathBuf::from("/nonexistent/channels.json"),
+            },
+            ..Default::default()
+        };
+        assert!(BackendRally::new(config).await.is_none());
+    }
+}
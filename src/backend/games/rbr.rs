@@ -0,0 +1,156 @@
+// Richard Burns Rally with the RSF plugin sends one packet per physics step to localhost once telemetry is enabled.
+// The car's orientation and motion come in degrees and in car space, so they are converted to radians and world space here.
+// Stage progress is reported as the lap distance and the track (stage) length, like the other rally games.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::rbr::*;
+use super::listen::{ListenConfig, TelemetrySocket};
+
+const KELVIN: f32 = 273.15;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RbrConfig {
+    pub listen: ListenConfig,
+}
+
+impl Default for RbrConfig {
+    fn default() -> Self {
+        Self {
+            listen: ListenConfig::local(DEFAULT_PORT),
+        }
+    }
+}
+
+pub struct BackendRbr {
+    socket: TelemetrySocket,
+
+    telemetry: Telemetry,
+}
+
+impl BackendRbr {
+    pub async fn new(config: RbrConfig) -> Option<Self> {
+        match config.listen.bind().await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            },
+            Ok(socket) => {
+                Some(Self {
+                    socket,
+
+                    telemetry: Telemetry {
+                        game: "Richard Burns Rally",
+                        ..Default::default()
+                    },
+                })
+            }
+        }
+    }
+
+    fn apply(&mut self, raw: &DataRbr) {
+        let telemetry = &mut self.telemetry;
+        let car = &raw.car;
+
+        telemetry.general = TelemetryGeneral {
+            gear: raw.gear(),
+            fuel: 0.0, // Not reported
+            fuel_capacity: None,
+            speed: car.speed.abs() / 3.6,
+        };
+        telemetry.engine.rpm = car.engine.rpm.max(0.0) as usize;
+        telemetry.engine.engine_temp = Some(car.engine.engine_coolant_temperature - KELVIN);
+        telemetry.input = TelemetryInput {
+            throttle: raw.control.throttle,
+            brake: raw.control.brake,
+            clutch: raw.control.clutch,
+        };
+
+        // RBR's yaw is a compass heading, ours goes anticlockwise
+        let heading = -car.yaw.to_radians();
+        let (sin, cos) = heading.sin_cos();
+        let to_world = |right: f32, forward: f32, up: f32| [right * cos - forward * sin, right * sin + forward * cos, up];
+        let (velocities, accelerations) = (&car.velocities, &car.accelerations);
+        telemetry.motion = TelemetryMotion {
+            heading,
+            pitch: car.pitch.to_radians(),
+            roll: car.roll.to_radians(),
+            angular_velocity: to_world(velocities.pitch.to_radians(), velocities.roll.to_radians(), -velocities.yaw.to_radians()),
+            acceleration: to_world(accelerations.sway, accelerations.surge, accelerations.heave),
+            velocity: to_world(velocities.sway, velocities.surge, velocities.heave),
+            position: car.position.map(|v| v as f64),
+        };
+
+        telemetry.wheels = car.suspension.iter().map(|corner| TelemetryWheel {
+            suspension_travel: corner.spring_deflection,
+            angular_velocity: 0.0, // Not reported
+            vertical_load: corner.strut_force,
+            tyre_temp: Some(corner.tyre.temperature - KELVIN),
+            tyre_pressure: Some(corner.tyre.pressure / 100.0),
+            brake_temp: Some(corner.brake_disk.temperature - KELVIN),
+            ..Default::default()
+        }).collect();
+
+        let stage = &raw.stage;
+        telemetry.timing.current_lap_time = Some(stage.race_time);
+        telemetry.timing.lap_distance = Some(stage.drive_line_location);
+        telemetry.timing.location = CarLocation::Track;
+        let length = stage.drive_line_location + stage.distance_to_end;
+        telemetry.session.track_length = if length > 0.0 { Some(length) } else { None };
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendRbr {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            match DataRbr::parse(&buf[..n]) {
+                Ok(raw) => {
+                    self.apply(&raw);
+                    return Some(self.telemetry.clone());
+                },
+                Err(e) => warn!("Dropping invalid RBR packet: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::games::GameBackend;
+    use crate::backend::protocols::rbr::tests::on_stage;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn decodes_stage_telemetry() {
+        let mut backend = BackendRbr::new(RbrConfig { listen: ListenConfig::local(0) }).await.unwrap();
+        let port = backend.socket.local_addr().unwrap().port();
+
+        let game = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        game.send_to(&[0u8; 16], ("127.0.0.1", port)).await.unwrap();
+        game.send_to(&on_stage(6100.0, 0), ("127.0.0.1", port)).await.unwrap();
+        let telemetry = backend.next_event().await.unwrap();
+        assert_eq!(telemetry.game, "Richard Burns Rally");
+        assert_eq!(telemetry.general.gear, -1);
+        assert_eq!(telemetry.general.speed, 30.0);
+        assert_eq!(telemetry.engine.rpm, 6100);
+        assert!((telemetry.engine.engine_temp.unwrap() - 90.0).abs() < 1e-3);
+        assert!((telemetry.motion.heading + std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        // Driving east at 30 m/s
+        assert!((telemetry.motion.velocity[0] - 30.0).abs() < 1e-3 && telemetry.motion.velocity[1].abs() < 1e-3);
+        assert_eq!(telemetry.wheels.len(), 4);
+        assert_eq!(telemetry.wheels[3].suspension_travel, 0.08);
+        assert_eq!(telemetry.wheels[3].vertical_load, 3500.0);
+        assert_eq!(telemetry.wheels[3].tyre_pressure, Some(2.0));
+        assert!((telemetry.wheels[0].brake_temp.unwrap() - 300.0).abs() < 1e-3);
+        assert_eq!(telemetry.timing.lap_distance, Some(2500.0));
+        assert_eq!(telemetry.session.track_length, Some(10000.0));
+    }
+}
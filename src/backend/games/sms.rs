@@ -0,0 +1,229 @@
+// Project CARS 2 and Automobilista 2 broadcast "SMS UDP" once it's enabled in the game's system options, with the protocol version set to "Project CARS 2".
+// The game broadcasts to the whole network, so unlike the other backends this one can't listen on localhost only.
+// Every packet type only covers part of the picture, so they are merged into the telemetry as they arrive.
+// Participant data is indexed by participant, the viewed participant comes with the car physics.
+
+use async_trait::async_trait;
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::*;
+use crate::backend::protocols::sms::*;
+use super::listen::{ListenConfig, TelemetrySocket};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmsConfig {
+    pub listen: ListenConfig,
+}
+
+impl Default for SmsConfig {
+    fn default() -> Self {
+        Self {
+            listen: ListenConfig::any(DEFAULT_PORT),
+        }
+    }
+}
+
+pub struct BackendSms {
+    socket: TelemetrySocket,
+
+    viewed_participant: Option<usize>,
+    local_participant: Option<usize>,
+    formation_lap: bool,
+    participant_names: Vec<String>,
+    telemetry: Telemetry,
+}
+
+impl BackendSms {
+    pub async fn new(config: SmsConfig, game: &'static str) -> Option<Self> {
+        match config.listen.bind().await {
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            },
+            Ok(socket) => {
+                Some(Self {
+                    socket,
+
+                    viewed_participant: None,
+                    local_participant: None,
+                    formation_lap: false,
+                    participant_names: vec![String::new(); MAX_PARTICIPANTS],
+                    telemetry: Telemetry {
+                        game,
+                        ..Default::default()
+                    },
+                })
+            }
+        }
+    }
+
+    /// Merges a packet into the latest telemetry.
+    /// Returns false if the packet couldn't be decoded.
+    fn handle_packet(&mut self, buf: &[u8]) -> bool {
+        let raw = match DataSms::parse(buf) {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!("Dropping invalid SMS packet: {e}");
+                return false;
+            },
+        };
+        let telemetry = &mut self.telemetry;
+        // Timing follows the viewed participant, or the local player if the game doesn't say
+        let player = self.viewed_participant.or(self.local_participant);
+
+        match raw.packet {
+            SmsPacket::Telemetry(car) => {
+                let viewed_participant = usize::try_from(car.viewed_participant_index).ok();
+                if viewed_participant != self.viewed_participant {
+                    if let Some(name) = viewed_participant.and_then(|i| self.participant_names.get(i)) {
+                        debug!("Now viewing participant: {name}");
+                    }
+                    self.viewed_participant = viewed_participant;
+                }
+
+                telemetry.general = TelemetryGeneral {
+                    gear: car.gear(),
+                    fuel: car.fuel_level,
+                    fuel_capacity: if car.fuel_capacity > 0 { Some(car.fuel_capacity as f32) } else { None },
+                    speed: car.speed,
+                };
+                telemetry.engine = TelemetryEngine {
+                    rpm: car.rpm as usize,
+                    max_rpm: if car.max_rpm > 0 { Some(car.max_rpm as usize) } else { None },
+                    idle_rpm: None,
+                    shift_rpm: None,
+                    turbo: None, // The boost pressure's unit isn't documented
+                    engine_temp: Some(car.water_temp as f32),
+                    oil_temp: Some(car.oil_temp as f32),
+                    oil_pressure: Some(car.oil_pressure as f32 / 100.0),
+                };
+                telemetry.input = TelemetryInput {
+                    throttle: car.throttle as f32 / 255.0,
+                    brake: car.brake as f32 / 255.0,
+                    clutch: car.clutch as f32 / 255.0,
+                };
+                telemetry.lights.available = DashLights::FULL_BEAM | DashLights::ENGINE | DashLights::PIT_SPEED | DashLights::ABS | DashLights::HANDBRAKE;
+                for (flag, light) in [
+                    (CAR_HEADLIGHT, DashLights::FULL_BEAM),
+                    (CAR_ENGINE_WARNING, DashLights::ENGINE),
+                    (CAR_SPEED_LIMITER, DashLights::PIT_SPEED),
+                    (CAR_ABS, DashLights::ABS),
+                    (CAR_HANDBRAKE, DashLights::HANDBRAKE),
+                ] {
+                    telemetry.lights.active.set(light, car.car_flags & flag != 0);
+                }
+
+                // SMS uses y up and z forward, we use z up and y forward
+                let [pitch, heading, roll] = car.orientation;
+                let [x, y, z] = car.full_position;
+                let to_world = |[x, y, z]: [f32; 3]| [x, z, y];
+                telemetry.motion = TelemetryMotion {
+                    heading,
+                    pitch,
+                    roll,
+                    angular_velocity: to_world(car.angular_velocity),
+                    acceleration: to_world(car.world_acceleration),
+                    velocity: to_world(car.world_velocity),
+                    position: [x as f64, z as f64, y as f64],
+                };
+
+                telemetry.wheels = (0..4).map(|i| TelemetryWheel {
+                    suspension_travel: car.suspension_travel[i],
+                    angular_velocity: car.tyre_rps[i] * std::f32::consts::TAU,
+                    speed: None,
+                    vertical_load: 0.0, // Not reported
+                    slip_ratio: 0.0,    // Not reported
+                    slip_angle: 0.0,    // Not reported
+                    tyre_temp: Some(car.tyre_temp[i] as f32),
+                    tyre_pressure: None, // The air pressure's unit isn't documented
+                    brake_temp: Some(car.brake_temp[i] as f32),
+                }).collect();
+            },
+            SmsPacket::RaceData(race) => {
+                telemetry.session.track_length = if race.track_length > 0.0 { Some(race.track_length) } else { None };
+                telemetry.session.total_laps = race.laps();
+            },
+            SmsPacket::Participants(participants) => {
+                for (index, name) in participants.indices.iter().zip(participants.names) {
+                    if let Some(slot) = self.participant_names.get_mut(*index as usize) {
+                        *slot = name;
+                    }
+                }
+            },
+            SmsPacket::Timings(timings) => {
+                self.local_participant = Some(timings.local_participant_index as usize);
+                let Some(player) = player.or(self.local_participant) else { return false };
+                let Some(info) = timings.participants.get(player) else { return false };
+                let local = self.local_participant == Some(player);
+                let split = |t: f32| if local && t >= 0.0 { Some(t) } else { None };
+
+                telemetry.session.time_left = if timings.event_time_remaining >= 0.0 { Some(timings.event_time_remaining) } else { None };
+                telemetry.session.phase = match info.race_state() {
+                    _ if self.formation_lap => SessionPhase::PreSession,
+                    1 => SessionPhase::PreSession,
+                    2 => SessionPhase::Running,
+                    3..=6 => SessionPhase::Finished,
+                    _ => SessionPhase::Unknown,
+                };
+                telemetry.timing = TelemetryTiming {
+                    lap: Some(info.current_lap as u32),
+                    position: if info.position() > 0 { Some(info.position() as u32) } else { None },
+                    current_lap_time: if info.current_time >= 0.0 { Some(info.current_time) } else { None },
+                    delta_to_car_ahead: split(timings.split_time_ahead),
+                    lap_distance: Some(info.current_lap_distance as f32),
+                    location: match info.pit_mode() {
+                        0 => CarLocation::Track,
+                        1 => CarLocation::PitEntry,
+                        2 | 4 => CarLocation::PitLane,
+                        3 | 5 => CarLocation::PitExit,
+                        _ => CarLocation::Unknown,
+                    },
+                    // Lap times come with the time stats
+                    last_lap_time: telemetry.timing.last_lap_time,
+                    best_lap_time: telemetry.timing.best_lap_time,
+                    ..Default::default()
+                };
+            },
+            SmsPacket::GameState(state) => {
+                telemetry.session.kind = match state.session() {
+                    1 | 2 => SessionKind::Practice,
+                    3 => SessionKind::Qualifying,
+                    4 | 5 => SessionKind::Race,
+                    6 => SessionKind::TimeTrial,
+                    _ => SessionKind::Unknown,
+                };
+                self.formation_lap = state.session() == 4;
+                if matches!(state.game(), 0 | 1 | 7) {
+                    // Back in the menus
+                    telemetry.session.phase = SessionPhase::PostSession;
+                }
+                telemetry.session.air_temp = Some(state.ambient_temperature as f32);
+                telemetry.session.track_temp = Some(state.track_temperature as f32);
+            },
+            SmsPacket::TimeStats(stats) => {
+                let Some(player) = player else { return false };
+                let Some(stats) = stats.participants.get(player) else { return false };
+                let lap_time = |t: f32| if t > 0.0 { Some(t) } else { None };
+                telemetry.timing.last_lap_time = lap_time(stats.last_lap_time);
+                telemetry.timing.best_lap_time = lap_time(stats.fastest_lap_time);
+            },
+            SmsPacket::Other => return false,
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl super::GameBackend for BackendSms {
+    async fn next_event(&mut self) -> Option<Telemetry> {
+        let mut buf = [0u8; 2048];
+        loop {
+            let n = self.socket.recv(&mut buf).await.ok()?;
+            if self.handle_packet(&buf[..n]) {
+                return Some(self.telemetry.clone());
+            }
+        }
+    }
+}
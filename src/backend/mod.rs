@@ -1,13 +1,17 @@
-use tokio::sync::mpsc;
+use tokio::sync::watch;
 
-use crate::telemetry::Telemetry;
+use crate::telemetry::{Telemetry, TelemetryFrame};
 
 mod games;
+mod protocols;
 
-use games::GameBackend;
+use games::{GameBackend, Detector};
 
-pub async fn main(tx: mpsc::Sender<Telemetry>) {
-    let mut backend = Backend::new(tx);
+pub use games::{GamesConfig, game_status};
+
+/// Publishes telemetry as the latest value only, a slow reader skips frames instead of falling behind
+pub async fn main(tx: watch::Sender<TelemetryFrame>, config: watch::Receiver<GamesConfig>) {
+    let mut backend = Backend::new(tx, config);
 
     loop {
         backend.process().await;
@@ -15,19 +19,31 @@ pub async fn main(tx: mpsc::Sender<Telemetry>) {
 }
 
 struct Backend {
-    tx: mpsc::Sender<Telemetry>,
+    tx: watch::Sender<TelemetryFrame>,
     game_backend: Option<Box<dyn GameBackend + Send>>,
+    detector: Detector,
+    config: watch::Receiver<GamesConfig>,
 }
 
 impl Backend {
-    fn new(tx: mpsc::Sender<Telemetry>) -> Self {
+    fn new(tx: watch::Sender<TelemetryFrame>, mut config: watch::Receiver<GamesConfig>) -> Self {
+        let detector = Detector::new(config.borrow_and_update().clone());
         Self {
             tx,
             game_backend: None,
+            detector,
+            config,
         }
     }
 
     async fn process(&mut self) {
+        if self.config.has_changed().unwrap_or(false) {
+            // Dropping the game backend releases its sockets, so the next detection cycle binds with the new settings
+            info!("Game settings changed, reconnecting");
+            self.game_backend = None;
+            self.detector = Detector::new(self.config.borrow_and_update().clone());
+        }
+
         if let Some(game_backend) = self.game_backend.as_mut() {
             tokio::select! {
                 _ = tokio::time::sleep(tokio::time::Duration::from_millis(500)) => {
@@ -36,12 +52,7 @@ impl Backend {
                 },
                 result = game_backend.next_event() => {
                     if let Some(telemetry) = result {
-                        if self.tx.capacity() == self.tx.max_capacity() {
-                            if let Err(e) = self.tx.send(telemetry).await {
-                                error!("Error sending telemetry: {:?}", e);
-                                tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
-                            }
-                        }
+                        self.publish(telemetry);
                     } else {
                         debug!("Game backend has returned None! If the game backend stops reporting data to the backend, the game backend is considered no longer working and we will look for a new backend.");
                         self.game_backend = None;
@@ -50,12 +61,42 @@ impl Backend {
             }
         } else {
             tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
-            self.game_backend = games::find_next_backend().await;
-            if self.tx.capacity() == self.tx.max_capacity() {
-                if let Err(e) = self.tx.send(Telemetry::default()).await {
-                    error!("Error sending telemetry: {:?}", e);
-                }
+            self.game_backend = self.detector.find_next_backend().await;
+            // Readers only need to hear once that the game is gone
+            if !self.tx.borrow().telemetry.game.is_empty() {
+                self.publish(Telemetry::default());
             }
         }
     }
+
+    /// Replaces the latest frame, even when nobody is reading right now
+    fn publish(&self, telemetry: Telemetry) {
+        self.tx.send_modify(|frame| {
+            frame.sequence += 1;
+            frame.telemetry = telemetry;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_see_the_latest_frame() {
+        let (tx, mut rx) = watch::channel(TelemetryFrame::default());
+        let (_config_tx, config_rx) = watch::channel(GamesConfig::default());
+        let backend = Backend::new(tx, config_rx);
+        for rpm in [1000, 2000, 3000] {
+            let mut telemetry = Telemetry { game: "Test", ..Default::default() };
+            telemetry.engine.rpm = rpm;
+            backend.publish(telemetry);
+        }
+
+        assert!(rx.has_changed().unwrap());
+        let frame = rx.borrow_and_update().clone();
+        assert_eq!(frame.sequence, 3);
+        assert_eq!(frame.telemetry.engine.rpm, 3000);
+        assert!(!rx.has_changed().unwrap());
+    }
 }
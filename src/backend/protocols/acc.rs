@@ -0,0 +1,516 @@
+//! Assetto Corsa Competizione's broadcasting protocol, as documented by Kunos' broadcasting SDK.
+//! A client registers with ACC's broadcasting port and then receives realtime updates for the session and every car.
+//! Strings are prefixed with their length as a u16 and encoded as UTF-8.
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 9000;
+pub const PROTOCOL_VERSION: u8 = 4;
+
+// Outbound message types
+pub const REGISTER_COMMAND_APPLICATION: u8 = 1;
+pub const UNREGISTER_COMMAND_APPLICATION: u8 = 9;
+pub const REQUEST_ENTRY_LIST: u8 = 10;
+pub const REQUEST_TRACK_DATA: u8 = 11;
+
+// Inbound message types
+pub const REGISTRATION_RESULT: u8 = 1;
+pub const REALTIME_UPDATE: u8 = 2;
+pub const REALTIME_CAR_UPDATE: u8 = 3;
+pub const ENTRY_LIST: u8 = 4;
+pub const TRACK_DATA: u8 = 5;
+pub const ENTRY_LIST_CAR: u8 = 6;
+pub const BROADCASTING_EVENT: u8 = 7;
+
+/// Lap and split times use this value when there is no time (yet)
+pub const NO_TIME: i32 = i32::MAX;
+
+pub fn encode_register(display_name: &str, connection_password: &str, update_interval_ms: i32, command_password: &str) -> Vec<u8> {
+    let mut buf = vec![REGISTER_COMMAND_APPLICATION, PROTOCOL_VERSION];
+    write_string(&mut buf, display_name);
+    write_string(&mut buf, connection_password);
+    buf.extend_from_slice(&update_interval_ms.to_le_bytes());
+    write_string(&mut buf, command_password);
+    buf
+}
+
+pub fn encode_unregister(connection_id: i32) -> Vec<u8> {
+    encode_request(UNREGISTER_COMMAND_APPLICATION, connection_id)
+}
+
+pub fn encode_request_entry_list(connection_id: i32) -> Vec<u8> {
+    encode_request(REQUEST_ENTRY_LIST, connection_id)
+}
+
+pub fn encode_request_track_data(connection_id: i32) -> Vec<u8> {
+    encode_request(REQUEST_TRACK_DATA, connection_id)
+}
+
+fn encode_request(message_type: u8, connection_id: i32) -> Vec<u8> {
+    let mut buf = vec![message_type];
+    buf.extend_from_slice(&connection_id.to_le_bytes());
+    buf
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Lap {
+    pub lap_time_ms: i32,       // NO_TIME if there is no time
+    pub car_index: u16,
+    pub driver_index: u16,
+    pub splits: Vec<i32>,       // Milliseconds, NO_TIME for sectors that haven't been driven
+    pub is_invalid: bool,
+    pub is_valid_for_best: bool,
+    pub is_outlap: bool,
+    pub is_inlap: bool,
+}
+
+impl Lap {
+    /// Lap time in seconds, None if there is no time
+    pub fn time(&self) -> Option<f32> {
+        if self.lap_time_ms == NO_TIME || self.lap_time_ms <= 0 {
+            None
+        } else {
+            Some(self.lap_time_ms as f32 / 1000.0)
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RegistrationResult {
+    pub connection_id: i32,
+    pub success: bool,
+    pub is_readonly: bool,      // True if the command password was wrong or empty
+    pub error_message: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RealtimeUpdate {
+    pub event_index: u16,
+    pub session_index: u16,
+    pub session_type: u8,       // 0 = practice, 4 = qualifying, 9 = superpole, 10 = race, 11 = hotlap, 12 = hotstint, 13 = hotlap superpole, 14 = replay
+    pub phase: u8,              // 0 = none, 1 = starting, 2 = pre formation, 3 = formation lap, 4 = pre session, 5 = session, 6 = session over, 7 = post session, 8 = result UI
+    pub session_time_ms: f32,
+    pub session_end_time_ms: f32, // Time remaining in the session
+    pub focused_car_index: i32,
+    pub active_camera_set: String,
+    pub active_camera: String,
+    pub current_hud_page: String,
+    pub is_replay_playing: bool,
+    pub replay_session_time: Option<f32>,
+    pub replay_remaining_time: Option<f32>,
+    pub time_of_day_ms: f32,
+    pub ambient_temp: i8,       // Celsius
+    pub track_temp: i8,         // Celsius
+    pub clouds: u8,             // 0-10
+    pub rain_level: u8,         // 0-10
+    pub wetness: u8,            // 0-10
+    pub best_session_lap: Lap,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RealtimeCarUpdate {
+    pub car_index: u16,
+    pub driver_index: u16,
+    pub driver_count: u8,
+    pub gear: i8,               // -1 = reverse, 0 = neutral, 1 = first, etc
+    pub world_pos_x: f32,       // Meters
+    pub world_pos_y: f32,       // Meters
+    pub yaw: f32,               // Radians
+    pub car_location: u8,       // 0 = none, 1 = track, 2 = pitlane, 3 = pit entry, 4 = pit exit
+    pub kmh: u16,
+    pub position: u16,          // Official position, starting at 1
+    pub cup_position: u16,
+    pub track_position: u16,
+    pub spline_position: f32,   // 0-1 around the track
+    pub laps: u16,              // Completed laps
+    pub delta_ms: i32,          // Realtime delta to the best session lap
+    pub best_session_lap: Lap,
+    pub last_lap: Lap,
+    pub current_lap: Lap,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntryList {
+    pub connection_id: i32,
+    pub car_indexes: Vec<u16>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TrackData {
+    pub connection_id: i32,
+    pub track_name: String,
+    pub track_id: i32,
+    pub track_meters: i32,
+    pub camera_sets: Vec<(String, Vec<String>)>,
+    pub hud_pages: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DriverInfo {
+    pub first_name: String,
+    pub last_name: String,
+    pub short_name: String,
+    pub category: u8,
+    pub nationality: u16,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntryListCar {
+    pub car_index: u16,
+    pub car_model_type: u8,
+    pub team_name: String,
+    pub race_number: i32,
+    pub cup_category: u8,
+    pub current_driver_index: u8,
+    pub nationality: u16,
+    pub drivers: Vec<DriverInfo>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BroadcastingEvent {
+    pub event_type: u8,
+    pub message: String,
+    pub time_ms: i32,
+    pub car_index: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccMessage {
+    RegistrationResult(RegistrationResult),
+    RealtimeUpdate(RealtimeUpdate),
+    RealtimeCarUpdate(RealtimeCarUpdate),
+    EntryList(EntryList),
+    TrackData(TrackData),
+    EntryListCar(EntryListCar),
+    BroadcastingEvent(BroadcastingEvent),
+}
+
+impl AccMessage {
+    /// Decodes a single message received from ACC
+    pub fn parse(buf: &[u8]) -> Result<Self, AccError> {
+        let mut r = ByteReader::new(buf);
+        let message_type = r.u8().ok_or(AccError::TooShort(buf.len()))?;
+        let message = match message_type {
+            REGISTRATION_RESULT => Self::read_registration_result(&mut r).map(Self::RegistrationResult),
+            REALTIME_UPDATE => Self::read_realtime_update(&mut r).map(Self::RealtimeUpdate),
+            REALTIME_CAR_UPDATE => Self::read_realtime_car_update(&mut r).map(Self::RealtimeCarUpdate),
+            ENTRY_LIST => Self::read_entry_list(&mut r).map(Self::EntryList),
+            TRACK_DATA => Self::read_track_data(&mut r).map(Self::TrackData),
+            ENTRY_LIST_CAR => Self::read_entry_list_car(&mut r).map(Self::EntryListCar),
+            BROADCASTING_EVENT => Self::read_broadcasting_event(&mut r).map(Self::BroadcastingEvent),
+            t => return Err(AccError::UnknownMessage(t)),
+        };
+        message.ok_or(AccError::TooShort(buf.len()))
+    }
+
+    fn read_registration_result(r: &mut ByteReader) -> Option<RegistrationResult> {
+        Some(RegistrationResult {
+            connection_id: r.i32()?,
+            success: r.u8()? > 0,
+            is_readonly: r.u8()? == 0,
+            error_message: read_string(r)?,
+        })
+    }
+
+    fn read_realtime_update(r: &mut ByteReader) -> Option<RealtimeUpdate> {
+        let mut update = RealtimeUpdate {
+            event_index: r.u16()?,
+            session_index: r.u16()?,
+            session_type: r.u8()?,
+            phase: r.u8()?,
+            session_time_ms: r.f32()?,
+            session_end_time_ms: r.f32()?,
+            focused_car_index: r.i32()?,
+            active_camera_set: read_string(r)?,
+            active_camera: read_string(r)?,
+            current_hud_page: read_string(r)?,
+            is_replay_playing: r.u8()? > 0,
+            ..Default::default()
+        };
+        if update.is_replay_playing {
+            update.replay_session_time = Some(r.f32()?);
+            update.replay_remaining_time = Some(r.f32()?);
+        }
+        Some(RealtimeUpdate {
+            time_of_day_ms: r.f32()?,
+            ambient_temp: r.i8()?,
+            track_temp: r.i8()?,
+            clouds: r.u8()?,
+            rain_level: r.u8()?,
+            wetness: r.u8()?,
+            best_session_lap: read_lap(r)?,
+            ..update
+        })
+    }
+
+    fn read_realtime_car_update(r: &mut ByteReader) -> Option<RealtimeCarUpdate> {
+        Some(RealtimeCarUpdate {
+            car_index: r.u16()?,
+            driver_index: r.u16()?,
+            driver_count: r.u8()?,
+            gear: (r.u8()? as i16 - 2) as i8, // Sent as reverse = 1, neutral = 2, first = 3, etc
+            world_pos_x: r.f32()?,
+            world_pos_y: r.f32()?,
+            yaw: r.f32()?,
+            car_location: r.u8()?,
+            kmh: r.u16()?,
+            position: r.u16()?,
+            cup_position: r.u16()?,
+            track_position: r.u16()?,
+            spline_position: r.f32()?,
+            laps: r.u16()?,
+            delta_ms: r.i32()?,
+            best_session_lap: read_lap(r)?,
+            last_lap: read_lap(r)?,
+            current_lap: read_lap(r)?,
+        })
+    }
+
+    fn read_entry_list(r: &mut ByteReader) -> Option<EntryList> {
+        let connection_id = r.i32()?;
+        let count = r.u16()?;
+        Some(EntryList {
+            connection_id,
+            car_indexes: (0..count).map(|_| r.u16()).collect::<Option<_>>()?,
+        })
+    }
+
+    fn read_track_data(r: &mut ByteReader) -> Option<TrackData> {
+        let connection_id = r.i32()?;
+        let track_name = read_string(r)?;
+        let track_id = r.i32()?;
+        let track_meters = r.i32()?;
+        let camera_set_count = r.u8()?;
+        let camera_sets = (0..camera_set_count).map(|_| {
+            let name = read_string(r)?;
+            let camera_count = r.u8()?;
+            let cameras = (0..camera_count).map(|_| read_string(r)).collect::<Option<_>>()?;
+            Some((name, cameras))
+        }).collect::<Option<_>>()?;
+        let hud_page_count = r.u8()?;
+        Some(TrackData {
+            connection_id,
+            track_name,
+            track_id,
+            track_meters,
+            camera_sets,
+            hud_pages: (0..hud_page_count).map(|_| read_string(r)).collect::<Option<_>>()?,
+        })
+    }
+
+    fn read_entry_list_car(r: &mut ByteReader) -> Option<EntryListCar> {
+        let car = EntryListCar {
+            car_index: r.u16()?,
+            car_model_type: r.u8()?,
+            team_name: read_string(r)?,
+            race_number: r.i32()?,
+            cup_category: r.u8()?,
+            current_driver_index: r.u8()?,
+            nationality: r.u16()?,
+            drivers: Vec::new(),
+        };
+        let driver_count = r.u8()?;
+        Some(EntryListCar {
+            drivers: (0..driver_count).map(|_| Some(DriverInfo {
+                first_name: read_string(r)?,
+                last_name: read_string(r)?,
+                short_name: read_string(r)?,
+                category: r.u8()?,
+                nationality: r.u16()?,
+            })).collect::<Option<_>>()?,
+            ..car
+        })
+    }
+
+    fn read_broadcasting_event(r: &mut ByteReader) -> Option<BroadcastingEvent> {
+        Some(BroadcastingEvent {
+            event_type: r.u8()?,
+            message: read_string(r)?,
+            time_ms: r.i32()?,
+            car_index: r.i32()?,
+        })
+    }
+}
+
+fn read_string(r: &mut ByteReader) -> Option<String> {
+    let len = r.u16()? as usize;
+    Some(String::from_utf8_lossy(r.slice(len)?).into_owned())
+}
+
+fn read_lap(r: &mut ByteReader) -> Option<Lap> {
+    let lap_time_ms = r.i32()?;
+    let car_index = r.u16()?;
+    let driver_index = r.u16()?;
+    let split_count = r.u8()?;
+    let splits = (0..split_count).map(|_| r.i32()).collect::<Option<_>>()?;
+    Some(Lap {
+        lap_time_ms,
+        car_index,
+        driver_index,
+        splits,
+        is_invalid: r.u8()? > 0,
+        is_valid_for_best: r.u8()? > 0,
+        is_outlap: r.u8()? > 0,
+        is_inlap: r.u8()? > 0,
+    })
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an acc broadcasting message
+pub enum AccError {
+    /// message ended before all of its fields were read
+    TooShort(usize),
+    /// message type we don't know about
+    UnknownMessage(u8),
+}
+
+impl std::fmt::Display for AccError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn lap(lap_time_ms: i32, car_index: u16) -> Vec<u8> {
+        let mut buf = lap_time_ms.to_le_bytes().to_vec();
+        buf.extend_from_slice(&car_index.to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        buf.push(3);
+        for split in [30_100, 31_200, NO_TIME] {
+            buf.extend_from_slice(&split.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0, 1, 0, 0]);
+        buf
+    }
+
+    pub fn registration_result(connection_id: i32, success: bool) -> Vec<u8> {
+        let mut buf = vec![REGISTRATION_RESULT];
+        buf.extend_from_slice(&connection_id.to_le_bytes());
+        buf.extend_from_slice(&[success as u8, 0]);
+        write_string(&mut buf, if success { "" } else { "Password wrong" });
+        buf
+    }
+
+    pub fn realtime_update(focused_car_index: i32) -> Vec<u8> {
+        let mut buf = vec![REALTIME_UPDATE];
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        buf.extend_from_slice(&[10, 5]);
+        buf.extend_from_slice(&600_000.0f32.to_le_bytes());
+        buf.extend_from_slice(&1_200_000.0f32.to_le_bytes());
+        buf.extend_from_slice(&focused_car_index.to_le_bytes());
+        write_string(&mut buf, "Drivable");
+        write_string(&mut buf, "Cockpit");
+        write_string(&mut buf, "Basic HUD");
+        buf.push(0);
+        buf.extend_from_slice(&50_400_000.0f32.to_le_bytes());
+        buf.extend_from_slice(&[22, 31, 1, 0, 0]);
+        buf.extend(lap(101_300, 7));
+        buf
+    }
+
+    pub fn realtime_car_update(car_index: u16) -> Vec<u8> {
+        let mut buf = vec![REALTIME_CAR_UPDATE];
+        buf.extend_from_slice(&car_index.to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        buf.extend_from_slice(&[1, 6]);
+        for v in [-512.5f32, 1_024.0, 1.57] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.push(1);
+        for v in [243u16, 3, 2, 3] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&0.25f32.to_le_bytes());
+        buf.extend_from_slice(&4u16.to_le_bytes());
+        buf.extend_from_slice(&(-350i32).to_le_bytes());
+        buf.extend(lap(101_800, car_index));
+        buf.extend(lap(102_050, car_index));
+        buf.extend(lap(25_400, car_index));
+        buf
+    }
+
+    pub fn track_data(connection_id: i32) -> Vec<u8> {
+        let mut buf = vec![TRACK_DATA];
+        buf.extend_from_slice(&connection_id.to_le_bytes());
+        write_string(&mut buf, "Monza");
+        buf.extend_from_slice(&11i32.to_le_bytes());
+        buf.extend_from_slice(&5_793i32.to_le_bytes());
+        buf.push(1);
+        write_string(&mut buf, "Drivable");
+        buf.push(2);
+        write_string(&mut buf, "Cockpit");
+        write_string(&mut buf, "Chase");
+        buf.push(1);
+        write_string(&mut buf, "Basic HUD");
+        buf
+    }
+
+    #[test]
+    fn encode_register_message() {
+        let buf = encode_register("Dysoon", "asd", 250, "");
+        assert_eq!(buf, [1, 4, 6, 0, b'D', b'y', b's', b'o', b'o', b'n', 3, 0, b'a', b's', b'd', 250, 0, 0, 0, 0, 0]);
+        assert_eq!(encode_unregister(7), [9, 7, 0, 0, 0]);
+    }
+
+    #[test]
+    fn parse_registration_result() {
+        let AccMessage::RegistrationResult(result) = AccMessage::parse(&registration_result(3, false)).unwrap() else { panic!("expected registration result") };
+        assert_eq!(result.connection_id, 3);
+        assert!(!result.success);
+        assert!(result.is_readonly);
+        assert_eq!(result.error_message, "Password wrong");
+    }
+
+    #[test]
+    fn parse_realtime_update() {
+        let AccMessage::RealtimeUpdate(update) = AccMessage::parse(&realtime_update(7)).unwrap() else { panic!("expected realtime update") };
+        assert_eq!(update.session_type, 10);
+        assert_eq!(update.phase, 5);
+        assert_eq!(update.focused_car_index, 7);
+        assert_eq!(update.current_hud_page, "Basic HUD");
+        assert_eq!(update.replay_session_time, None);
+        assert_eq!(update.track_temp, 31);
+        assert_eq!(update.best_session_lap.time(), Some(101.3));
+        assert_eq!(update.best_session_lap.splits, [30_100, 31_200, NO_TIME]);
+    }
+
+    #[test]
+    fn parse_realtime_car_update() {
+        let AccMessage::RealtimeCarUpdate(update) = AccMessage::parse(&realtime_car_update(7)).unwrap() else { panic!("expected car update") };
+        assert_eq!(update.car_index, 7);
+        assert_eq!(update.gear, 4);
+        assert_eq!(update.world_pos_x, -512.5);
+        assert_eq!(update.kmh, 243);
+        assert_eq!(update.position, 3);
+        assert_eq!(update.spline_position, 0.25);
+        assert_eq!(update.delta_ms, -350);
+        assert_eq!(update.last_lap.time(), Some(102.05));
+        assert_eq!(update.current_lap.time(), Some(25.4));
+    }
+
+    #[test]
+    fn parse_track_data() {
+        let AccMessage::TrackData(track) = AccMessage::parse(&track_data(1)).unwrap() else { panic!("expected track data") };
+        assert_eq!(track.track_name, "Monza");
+        assert_eq!(track.track_meters, 5_793);
+        assert_eq!(track.camera_sets, [("Drivable".to_string(), vec!["Cockpit".to_string(), "Chase".to_string()])]);
+        assert_eq!(track.hud_pages, ["Basic HUD"]);
+    }
+
+    #[test]
+    fn reject_invalid_messages() {
+        assert_eq!(AccMessage::parse(&[]), Err(AccError::TooShort(0)));
+        assert_eq!(AccMessage::parse(&[42, 0, 0]), Err(AccError::UnknownMessage(42)));
+        let buf = realtime_car_update(1);
+        assert_eq!(AccMessage::parse(&buf[..40]), Err(AccError::TooShort(40)));
+    }
+}
@@ -0,0 +1,286 @@
+//! Assetto Corsa's UDP remote telemetry.
+//! AC acts as the server: a client sends a handshake, receives the session info, then subscribes to `RTCarInfo` updates
+//! until it sends a dismiss. All structs are packed the way MSVC lays them out, including padding after bools/chars.
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 9996;
+pub const HANDSHAKE_RESPONSE_SIZE: usize = 408;
+pub const CAR_INFO_SIZE: usize = 328;
+
+pub const OPERATION_HANDSHAKE: i32 = 0;
+pub const OPERATION_SUBSCRIBE_UPDATE: i32 = 1;
+pub const OPERATION_DISMISS: i32 = 3;
+
+/// Encodes the packet a client sends to AC for each operation
+pub fn encode_handshaker(operation: i32) -> [u8; 12] {
+    let mut buf = [0u8; 12];
+    buf[0..4].copy_from_slice(&1i32.to_le_bytes()); // Identifier, 1 = eIPhoneDevice
+    buf[4..8].copy_from_slice(&1i32.to_le_bytes()); // Version
+    buf[8..12].copy_from_slice(&operation.to_le_bytes());
+    buf
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HandshakeResponse {
+    pub car_name: String,
+    pub driver_name: String,
+    pub identifier: i32,    // Always 4242 for now
+    pub version: i32,
+    pub track_name: String,
+    pub track_config: String,
+}
+
+impl HandshakeResponse {
+    pub fn parse(buf: &[u8]) -> Result<Self, AssettoCorsaError> {
+        if buf.len() < HANDSHAKE_RESPONSE_SIZE {
+            return Err(AssettoCorsaError::TooShort(buf.len()));
+        } else if buf.len() > HANDSHAKE_RESPONSE_SIZE {
+            return Err(AssettoCorsaError::UnexpectedLength(buf.len()));
+        }
+        Self::read(&mut ByteReader::new(buf)).ok_or(AssettoCorsaError::TooShort(buf.len()))
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            car_name: decode_wstr(&r.bytes::<100>()?),
+            driver_name: decode_wstr(&r.bytes::<100>()?),
+            identifier: r.i32()?,
+            version: r.i32()?,
+            track_name: decode_wstr(&r.bytes::<100>()?),
+            track_config: decode_wstr(&r.bytes::<100>()?),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RtCarInfo {
+    pub identifier: u8,         // 'a'
+    pub size: i32,              // Size of the struct in bytes
+    pub speed_kmh: f32,
+    pub speed_mph: f32,
+    pub speed_ms: f32,
+    pub is_abs_enabled: bool,
+    pub is_abs_in_action: bool,
+    pub is_tc_in_action: bool,
+    pub is_tc_enabled: bool,
+    pub is_in_pit: bool,
+    pub is_engine_limiter_on: bool,
+    pub acc_g_vertical: f32,
+    pub acc_g_horizontal: f32,  // Lateral
+    pub acc_g_frontal: f32,     // Longitudinal
+    pub lap_time: i32,          // Milliseconds
+    pub last_lap: i32,          // Milliseconds
+    pub best_lap: i32,          // Milliseconds
+    pub lap_count: i32,
+    pub gas: f32,               // 0-1
+    pub brake: f32,             // 0-1
+    pub clutch: f32,            // 0-1, 1 is fully engaged
+    pub engine_rpm: f32,
+    pub steer: f32,             // Degrees of steering wheel rotation
+    pub gear: i32,              // Reverse = 0, neutral = 1, first = 2, etc
+    pub cg_height: f32,         // Meters
+    pub wheel_angular_speed: [f32; 4], // Radians per second. Front left, front right, rear left, rear right
+    pub slip_angle: [f32; 4],   // Degrees
+    pub slip_angle_contact_patch: [f32; 4],
+    pub slip_ratio: [f32; 4],
+    pub tyre_slip: [f32; 4],
+    pub nd_slip: [f32; 4],
+    pub load: [f32; 4],         // Newton
+    pub dy: [f32; 4],
+    pub mz: [f32; 4],
+    pub tyre_dirty_level: [f32; 4],
+    pub camber_rad: [f32; 4],
+    pub tyre_radius: [f32; 4],  // Meters
+    pub tyre_loaded_radius: [f32; 4], // Meters
+    pub suspension_height: [f32; 4], // Meters
+    pub car_position_normalized: f32, // 0-1 around the track
+    pub car_slope: f32,
+    pub car_coordinates: [f32; 3], // Meters, x, y up, z
+}
+
+impl RtCarInfo {
+    pub fn parse(buf: &[u8]) -> Result<Self, AssettoCorsaError> {
+        if buf.len() < CAR_INFO_SIZE {
+            return Err(AssettoCorsaError::TooShort(buf.len()));
+        } else if buf.len() > CAR_INFO_SIZE {
+            return Err(AssettoCorsaError::UnexpectedLength(buf.len()));
+        }
+        let raw = Self::read(&mut ByteReader::new(buf)).ok_or(AssettoCorsaError::TooShort(buf.len()))?;
+        if raw.identifier != b'a' {
+            return Err(AssettoCorsaError::Malformed);
+        }
+        Ok(raw)
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        let f32x4 = |r: &mut ByteReader| Some([r.f32()?, r.f32()?, r.f32()?, r.f32()?]);
+        let identifier = r.u8()?;
+        r.skip(3)?; // Padding
+        let size = r.i32()?;
+        let (speed_kmh, speed_mph, speed_ms) = (r.f32()?, r.f32()?, r.f32()?);
+        let flags = r.bytes::<6>()?.map(|b| b != 0);
+        r.skip(2)?; // Padding
+        Some(Self {
+            identifier,
+            size,
+            speed_kmh,
+            speed_mph,
+            speed_ms,
+            is_abs_enabled: flags[0],
+            is_abs_in_action: flags[1],
+            is_tc_in_action: flags[2],
+            is_tc_enabled: flags[3],
+            is_in_pit: flags[4],
+            is_engine_limiter_on: flags[5],
+            acc_g_vertical: r.f32()?,
+            acc_g_horizontal: r.f32()?,
+            acc_g_frontal: r.f32()?,
+            lap_time: r.i32()?,
+            last_lap: r.i32()?,
+            best_lap: r.i32()?,
+            lap_count: r.i32()?,
+            gas: r.f32()?,
+            brake: r.f32()?,
+            clutch: r.f32()?,
+            engine_rpm: r.f32()?,
+            steer: r.f32()?,
+            gear: r.i32()?,
+            cg_height: r.f32()?,
+            wheel_angular_speed: f32x4(r)?,
+            slip_angle: f32x4(r)?,
+            slip_angle_contact_patch: f32x4(r)?,
+            slip_ratio: f32x4(r)?,
+            tyre_slip: f32x4(r)?,
+            nd_slip: f32x4(r)?,
+            load: f32x4(r)?,
+            dy: f32x4(r)?,
+            mz: f32x4(r)?,
+            tyre_dirty_level: f32x4(r)?,
+            camber_rad: f32x4(r)?,
+            tyre_radius: f32x4(r)?,
+            tyre_loaded_radius: f32x4(r)?,
+            suspension_height: f32x4(r)?,
+            car_position_normalized: r.f32()?,
+            car_slope: r.f32()?,
+            car_coordinates: [r.f32()?, r.f32()?, r.f32()?],
+        })
+    }
+}
+
+/// Decodes a fixed size UTF-16 string field.
+/// AC terminates these with a '%' or NUL and leaves garbage after it.
+fn decode_wstr(bytes: &[u8]) -> String {
+    let units = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|c| *c != 0);
+    let s: String = char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect();
+    match s.find('%') {
+        Some(end) => s[..end].to_string(),
+        None => s,
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an assetto corsa packet
+pub enum AssettoCorsaError {
+    /// packet is shorter than the struct it should contain
+    TooShort(usize),
+    /// packet is longer than the struct it should contain
+    UnexpectedLength(usize),
+    /// packet has the right size but contains values that make no sense
+    Malformed,
+}
+
+impl std::fmt::Display for AssettoCorsaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn wstr(s: &str) -> Vec<u8> {
+        let mut buf: Vec<u8> = s.encode_utf16().chain(Some('%' as u16)).flat_map(|c| c.to_le_bytes()).collect();
+        buf.resize(100, 0xcd);
+        buf
+    }
+
+    /// A handshake response as AC sends it
+    pub fn handshake_response() -> Vec<u8> {
+        let mut buf = wstr("ks_mazda_mx5_cup");
+        buf.extend(wstr("Driver"));
+        buf.extend_from_slice(&4242i32.to_le_bytes());
+        buf.extend_from_slice(&1i32.to_le_bytes());
+        buf.extend(wstr("magione"));
+        buf.extend(wstr(""));
+        buf
+    }
+
+    /// An `RTCarInfo` update in 3rd gear at 5500 rpm, with ABS kicking in
+    pub fn car_info() -> Vec<u8> {
+        let mut buf = vec![b'a', 0, 0, 0];
+        buf.extend_from_slice(&(CAR_INFO_SIZE as i32).to_le_bytes());
+        for v in [90.0f32, 55.9, 25.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&[1, 1, 0, 1, 0, 0, 0, 0]);
+        for v in [1.0f32, -0.8, 0.3] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [45_250i32, 92_100, 91_800, 4] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0.8f32, 0.2, 1.0, 5_500.0, -30.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&4i32.to_le_bytes());
+        buf.extend_from_slice(&0.35f32.to_le_bytes());
+        for array in 0..14 {
+            for wheel in 0..4 {
+                buf.extend_from_slice(&(array as f32 * 10.0 + wheel as f32).to_le_bytes());
+            }
+        }
+        for v in [0.5f32, 0.01, 120.0, 2.5, -340.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn parse_handshake_response() {
+        let raw = HandshakeResponse::parse(&handshake_response()).unwrap();
+        assert_eq!(raw.car_name, "ks_mazda_mx5_cup");
+        assert_eq!(raw.driver_name, "Driver");
+        assert_eq!(raw.identifier, 4242);
+        assert_eq!(raw.track_name, "magione");
+        assert_eq!(raw.track_config, "");
+    }
+
+    #[test]
+    fn parse_car_info() {
+        let raw = RtCarInfo::parse(&car_info()).unwrap();
+        assert_eq!(raw.size, CAR_INFO_SIZE as i32);
+        assert_eq!(raw.speed_ms, 25.0);
+        assert!(raw.is_abs_enabled && raw.is_abs_in_action && !raw.is_tc_in_action && raw.is_tc_enabled);
+        assert_eq!(raw.acc_g_horizontal, -0.8);
+        assert_eq!(raw.best_lap, 91_800);
+        assert_eq!(raw.lap_count, 4);
+        assert_eq!(raw.engine_rpm, 5_500.0);
+        assert_eq!(raw.gear, 4);
+        assert_eq!(raw.wheel_angular_speed, [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(raw.load, [60.0, 61.0, 62.0, 63.0]);
+        assert_eq!(raw.suspension_height, [130.0, 131.0, 132.0, 133.0]);
+        assert_eq!(raw.car_coordinates, [120.0, 2.5, -340.0]);
+    }
+
+    #[test]
+    fn reject_invalid_packets() {
+        let buf = car_info();
+        assert_eq!(RtCarInfo::parse(&buf[..200]), Err(AssettoCorsaError::TooShort(200)));
+        let mut wrong_identifier = buf.clone();
+        wrong_identifier[0] = b'b';
+        assert_eq!(RtCarInfo::parse(&wrong_identifier), Err(AssettoCorsaError::Malformed));
+        assert_eq!(HandshakeResponse::parse(&buf), Err(AssettoCorsaError::TooShort(CAR_INFO_SIZE)));
+    }
+}
@@ -0,0 +1,175 @@
+//! Codemasters "extradata" UDP telemetry, as sent by DiRT Rally 2.0 with `extradata="3"` in `hardware_settings_config.xml`.
+//! The packet is a flat array of 66 little-endian floats, even for values that are integers.
+//! Wheel arrays are ordered rear left, rear right, front left, front right.
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 20777;
+pub const PACKET_SIZE: usize = 66 * 4;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataDirtRally {
+    pub total_time: f32,                // Seconds
+    pub lap_time: f32,                  // Seconds, time into the stage
+    pub lap_distance: f32,              // Meters driven on the stage
+    pub total_distance: f32,            // Meters
+    pub position: [f32; 3],             // Meters in world space, x right, y up, z forward
+    pub speed: f32,                     // M/S
+    pub velocity: [f32; 3],             // M/S in world space
+    pub roll_vector: [f32; 3],          // Unit vector pointing to the right of the car, in world space
+    pub pitch_vector: [f32; 3],         // Unit vector pointing to the front of the car, in world space
+    pub suspension_position: [f32; 4],  // Millimeters
+    pub suspension_velocity: [f32; 4],  // Millimeters per second
+    pub wheel_speed: [f32; 4],          // M/S
+    pub throttle: f32,                  // 0-1
+    pub steer: f32,                     // -1 (left) to 1 (right)
+    pub brake: f32,                     // 0-1
+    pub clutch: f32,                    // 0-1
+    pub gear: f32,                      // 0 = neutral, 10 = reverse
+    pub g_force_lateral: f32,
+    pub g_force_longitudinal: f32,
+    pub lap: f32,                       // Starting at 0
+    pub engine_rate: f32,               // RPM / 10
+    pub sli_pro_support: f32,
+    pub car_position: f32,              // Starting at 1
+    pub kers_level: f32,
+    pub kers_max_level: f32,
+    pub drs: f32,
+    pub traction_control: f32,
+    pub anti_lock_brakes: f32,
+    pub fuel_in_tank: f32,              // Liters, always 0 in DiRT Rally 2.0
+    pub fuel_capacity: f32,             // Liters, always 0 in DiRT Rally 2.0
+    pub in_pits: f32,
+    pub sector: f32,
+    pub sector_1_time: f32,             // Seconds
+    pub sector_2_time: f32,             // Seconds
+    pub brake_temp: [f32; 4],           // Celsius
+    pub tyre_pressure: [f32; 4],        // PSI
+    pub laps_completed: f32,
+    pub total_laps: f32,
+    pub track_length: f32,              // Meters, the stage length
+    pub last_lap_time: f32,             // Seconds
+    pub max_rpm: f32,                   // RPM / 10
+    pub idle_rpm: f32,                  // RPM / 10
+    pub max_gears: f32,
+}
+
+impl DataDirtRally {
+    pub fn parse(buf: &[u8]) -> Result<Self, DirtRallyError> {
+        if buf.len() < PACKET_SIZE {
+            return Err(DirtRallyError::TooShort(buf.len()));
+        } else if buf.len() > PACKET_SIZE {
+            return Err(DirtRallyError::UnexpectedLength(buf.len()));
+        }
+        Self::read(&mut ByteReader::new(buf)).ok_or(DirtRallyError::TooShort(buf.len()))
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            total_time: r.f32()?,
+            lap_time: r.f32()?,
+            lap_distance: r.f32()?,
+            total_distance: r.f32()?,
+            position: r.array(ByteReader::f32)?,
+            speed: r.f32()?,
+            velocity: r.array(ByteReader::f32)?,
+            roll_vector: r.array(ByteReader::f32)?,
+            pitch_vector: r.array(ByteReader::f32)?,
+            suspension_position: r.array(ByteReader::f32)?,
+            suspension_velocity: r.array(ByteReader::f32)?,
+            wheel_speed: r.array(ByteReader::f32)?,
+            throttle: r.f32()?,
+            steer: r.f32()?,
+            brake: r.f32()?,
+            clutch: r.f32()?,
+            gear: r.f32()?,
+            g_force_lateral: r.f32()?,
+            g_force_longitudinal: r.f32()?,
+            lap: r.f32()?,
+            engine_rate: r.f32()?,
+            sli_pro_support: r.f32()?,
+            car_position: r.f32()?,
+            kers_level: r.f32()?,
+            kers_max_level: r.f32()?,
+            drs: r.f32()?,
+            traction_control: r.f32()?,
+            anti_lock_brakes: r.f32()?,
+            fuel_in_tank: r.f32()?,
+            fuel_capacity: r.f32()?,
+            in_pits: r.f32()?,
+            sector: r.f32()?,
+            sector_1_time: r.f32()?,
+            sector_2_time: r.f32()?,
+            brake_temp: r.array(ByteReader::f32)?,
+            tyre_pressure: r.array(ByteReader::f32)?,
+            laps_completed: r.f32()?,
+            total_laps: r.f32()?,
+            track_length: r.f32()?,
+            last_lap_time: r.f32()?,
+            max_rpm: r.f32()?,
+            idle_rpm: r.f32()?,
+            max_gears: r.f32()?,
+        })
+    }
+
+    /// Current gear, -1 for reverse and 0 for neutral
+    pub fn gear(&self) -> isize {
+        match self.gear as isize {
+            10 => -1,
+            gear => gear,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding a DiRT Rally packet
+pub enum DirtRallyError {
+    /// packet is shorter than the extradata=3 layout
+    TooShort(usize),
+    /// packet is longer than the extradata=3 layout, probably a different extradata setting
+    UnexpectedLength(usize),
+}
+
+impl std::fmt::Display for DirtRallyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_extradata_3() {
+        let mut values = [0f32; 66];
+        values[2] = 1234.5;
+        values[7] = 27.5;
+        values[17..21].copy_from_slice(&[12.0, 13.0, 10.0, 11.0]);
+        values[25..29].copy_from_slice(&[27.0, 27.5, 26.0, 26.5]);
+        values[33] = 10.0;
+        values[37] = 612.0;
+        values[61] = 9870.0;
+        values[63] = 780.0;
+        values[65] = 6.0;
+        let buf: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        let raw = DataDirtRally::parse(&buf).unwrap();
+        assert_eq!(raw.lap_distance, 1234.5);
+        assert_eq!(raw.speed, 27.5);
+        assert_eq!(raw.suspension_position, [12.0, 13.0, 10.0, 11.0]);
+        assert_eq!(raw.wheel_speed, [27.0, 27.5, 26.0, 26.5]);
+        assert_eq!(raw.gear(), -1);
+        assert_eq!(raw.engine_rate, 612.0);
+        assert_eq!(raw.track_length, 9870.0);
+        assert_eq!(raw.max_rpm, 780.0);
+        assert_eq!(raw.max_gears, 6.0);
+    }
+
+    #[test]
+    fn reject_other_extradata() {
+        // extradata=0 only sends the first 38 floats
+        assert_eq!(DataDirtRally::parse(&[0; 38 * 4]), Err(DirtRallyError::TooShort(152)));
+        assert_eq!(DataDirtRally::parse(&[0; PACKET_SIZE + 4]), Err(DirtRallyError::UnexpectedLength(PACKET_SIZE + 4)));
+    }
+}
@@ -0,0 +1,317 @@
+//! EA Sports WRC UDP telemetry.
+//! The game has no fixed layout: packets are described by a JSON "packet structure" file listing channel names,
+//! and the type of every channel is defined in the `channels.json` the game ships in its telemetry readme folder.
+//! Both live under `Documents/My Games/WRC/telemetry`. A structure may define several packets, they are told apart
+//! by their size and, if they start with one, their four character code.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 20777;
+
+/// Channel holding the four character code at the start of most packets
+const FOURCC_CHANNEL: &str = "packet_4cc";
+
+/// Four character codes of the packets the game sends
+const PACKET_FOURCCS: &[(&str, &[u8; 4])] = &[
+    ("session_start", b"sess"),
+    ("session_update", b"sesu"),
+    ("session_end", b"sese"),
+    ("session_pause", b"sesp"),
+    ("session_resume", b"sesr"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelType {
+    FourCC,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ChannelType {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "fourcc" => Self::FourCC,
+            "boolean" => Self::Bool,
+            "uint8" => Self::U8,
+            "uint16" => Self::U16,
+            "uint32" => Self::U32,
+            "uint64" => Self::U64,
+            "int8" => Self::I8,
+            "int16" => Self::I16,
+            "int32" => Self::I32,
+            "int64" => Self::I64,
+            "float32" => Self::F32,
+            "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bool | Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::FourCC | Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    /// Reads a value as a number. Four character codes read as their little-endian u32.
+    fn read(&self, r: &mut ByteReader) -> Option<f64> {
+        Some(match self {
+            Self::Bool | Self::U8 => r.u8()? as f64,
+            Self::U16 => r.u16()? as f64,
+            Self::FourCC | Self::U32 => r.u32()? as f64,
+            Self::U64 => r.u64()? as f64,
+            Self::I8 => r.i8()? as f64,
+            Self::I16 => r.i16()? as f64,
+            Self::I32 => r.i32()? as f64,
+            Self::I64 => r.i64()? as f64,
+            Self::F32 => r.f32()? as f64,
+            Self::F64 => r.f64()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketDefinition {
+    pub id: String,
+    pub channels: Vec<(String, ChannelType)>,
+    pub size: usize,
+}
+
+/// A loaded packet structure, ready to decode the packets it describes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketStructure {
+    pub id: String,
+    pub packets: Vec<PacketDefinition>,
+}
+
+/// A decoded packet. Values are keyed by channel name and converted to floats, booleans read as 0 or 1.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WrcPacket {
+    pub id: String,
+    pub values: HashMap<String, f64>,
+}
+
+impl WrcPacket {
+    pub fn get(&self, channel: &str) -> Option<f64> {
+        self.values.get(channel).copied()
+    }
+}
+
+#[derive(Deserialize)]
+struct StructureFile {
+    id: String,
+    #[serde(default)]
+    header: Option<PacketFile>,
+    packets: Vec<PacketFile>,
+}
+
+#[derive(Deserialize)]
+struct PacketFile {
+    #[serde(default)]
+    id: String,
+    channels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ChannelsFile {
+    channels: Vec<ChannelFile>,
+}
+
+#[derive(Deserialize)]
+struct ChannelFile {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+impl PacketStructure {
+    /// Builds a packet structure from the contents of a structure file and the game's channel definitions.
+    pub fn from_json(structure: &str, channels: &str) -> Result<Self, StructureError> {
+        let structure: StructureFile = serde_json::from_str(structure).map_err(|e| StructureError::InvalidJson(e.to_string()))?;
+        let channels: ChannelsFile = serde_json::from_str(channels).map_err(|e| StructureError::InvalidJson(e.to_string()))?;
+
+        let mut types = HashMap::new();
+        for channel in channels.channels {
+            let kind = ChannelType::from_name(&channel.kind).ok_or_else(|| StructureError::UnknownType(channel.kind.clone()))?;
+            types.insert(channel.id, kind);
+        }
+
+        let header = structure.header.map(|header| header.channels).unwrap_or_default();
+        let packets = structure.packets.into_iter().map(|packet| {
+            let channels = header.iter().chain(&packet.channels).map(|name| {
+                let kind = *types.get(name).ok_or_else(|| StructureError::UnknownChannel(name.clone()))?;
+                Ok((name.clone(), kind))
+            }).collect::<Result<Vec<_>, _>>()?;
+            Ok(PacketDefinition {
+                id: packet.id,
+                size: channels.iter().map(|(_, kind)| kind.size()).sum(),
+                channels,
+            })
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            id: structure.id,
+            packets,
+        })
+    }
+
+    /// Loads a structure file and the channel definitions from disk.
+    pub fn load(structure_path: &std::path::Path, channels_path: &std::path::Path) -> Result<Self, StructureError> {
+        let read = |path: &std::path::Path| std::fs::read_to_string(path).map_err(|e| StructureError::Io(format!("{}: {e}", path.display())));
+        Self::from_json(&read(structure_path)?, &read(channels_path)?)
+    }
+
+    /// Decodes a packet with whichever definition of this structure matches it.
+    pub fn parse(&self, buf: &[u8]) -> Result<WrcPacket, WrcError> {
+        let fourcc = buf.get(..4);
+        let definition = self.packets.iter().find(|packet| {
+            if packet.size != buf.len() {
+                return false;
+            }
+            // Packets of the same size can still be told apart by their code
+            match (packet.channels.first(), PACKET_FOURCCS.iter().find(|(id, _)| *id == packet.id)) {
+                (Some((name, _)), Some((_, expected))) if name == FOURCC_CHANNEL => fourcc == Some(&expected[..]),
+                _ => true,
+            }
+        }).ok_or(WrcError::NoMatchingPacket(buf.len()))?;
+
+        let mut r = ByteReader::new(buf);
+        let values = definition.channels.iter()
+            .map(|(name, kind)| kind.read(&mut r).map(|value| (name.clone(), value)))
+            .collect::<Option<_>>()
+            .ok_or(WrcError::NoMatchingPacket(buf.len()))?;
+        Ok(WrcPacket {
+            id: definition.id.clone(),
+            values,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error loading an EA WRC packet structure
+pub enum StructureError {
+    /// structure or channels file could not be read
+    Io(String),
+    /// structure or channels file isn't what we expect
+    InvalidJson(String),
+    /// channel type we can't decode
+    UnknownType(String),
+    /// structure uses a channel the channels file doesn't define
+    UnknownChannel(String),
+}
+
+impl std::fmt::Display for StructureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an EA WRC packet
+pub enum WrcError {
+    /// none of the packets in the structure match this size and code
+    NoMatchingPacket(usize),
+}
+
+impl std::fmt::Display for WrcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub const CHANNELS: &str = r#"{
+        "versions": { "schema": 1, "data": 3 },
+        "channels": [
+            { "id": "packet_4cc", "type": "fourcc", "description": "Four character code of the packet" },
+            { "id": "packet_uid", "type": "uint64" },
+            { "id": "vehicle_speed", "type": "float32", "units": "metre/second" },
+            { "id": "vehicle_gear_index", "type": "uint8" },
+            { "id": "vehicle_gear_index_neutral", "type": "uint8" },
+            { "id": "vehicle_gear_index_reverse", "type": "uint8" },
+            { "id": "vehicle_engine_rpm_current", "type": "float32" },
+            { "id": "vehicle_engine_rpm_max", "type": "float32" },
+            { "id": "stage_current_distance", "type": "float64" },
+            { "id": "stage_length", "type": "float64" },
+            { "id": "game_paused", "type": "boolean" }
+        ]
+    }"#;
+
+    pub const STRUCTURE: &str = r#"{
+        "versions": { "schema": 1, "data": 3 },
+        "id": "dysoon",
+        "packets": [
+            { "id": "session_update", "channels": [
+                "packet_4cc", "packet_uid", "vehicle_speed", "vehicle_gear_index", "vehicle_gear_index_neutral", "vehicle_gear_index_reverse",
+                "vehicle_engine_rpm_current", "vehicle_engine_rpm_max", "stage_current_distance", "stage_length"
+            ] },
+            { "id": "session_pause", "channels": ["packet_4cc", "packet_uid", "vehicle_speed", "game_paused", "vehicle_gear_index", "vehicle_gear_index_neutral", "vehicle_gear_index_reverse", "vehicle_engine_rpm_current", "vehicle_engine_rpm_max", "stage_current_distance", "stage_length"] }
+        ]
+    }"#;
+
+    pub fn session_update(gear: u8, rpm: f32, distance: f64) -> Vec<u8> {
+        let mut buf = b"sesu".to_vec();
+        buf.extend_from_slice(&42u64.to_le_bytes());
+        buf.extend_from_slice(&25.0f32.to_le_bytes());
+        buf.extend_from_slice(&[gear, 0, 7]);
+        buf.extend_from_slice(&rpm.to_le_bytes());
+        buf.extend_from_slice(&7500.0f32.to_le_bytes());
+        buf.extend_from_slice(&distance.to_le_bytes());
+        buf.extend_from_slice(&8800.0f64.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn load_structure() {
+        let structure = PacketStructure::from_json(STRUCTURE, CHANNELS).unwrap();
+        assert_eq!(structure.id, "dysoon");
+        assert_eq!(structure.packets[0].size, 4 + 8 + 4 + 3 + 4 + 4 + 8 + 8);
+        assert_eq!(structure.packets[0].channels[3], ("vehicle_gear_index".to_string(), ChannelType::U8));
+    }
+
+    #[test]
+    fn parse_packet() {
+        let structure = PacketStructure::from_json(STRUCTURE, CHANNELS).unwrap();
+        let packet = structure.parse(&session_update(3, 5400.0, 1234.5)).unwrap();
+        assert_eq!(packet.id, "session_update");
+        assert_eq!(packet.get("vehicle_gear_index"), Some(3.0));
+        assert_eq!(packet.get("vehicle_engine_rpm_current"), Some(5400.0));
+        assert_eq!(packet.get("stage_current_distance"), Some(1234.5));
+        assert_eq!(packet.get("packet_4cc"), Some(u32::from_le_bytes(*b"sesu") as f64));
+
+        // Same size as an update, but a different code
+        let mut buf = session_update(3, 5400.0, 1234.5);
+        buf.push(0);
+        buf[..4].copy_from_slice(b"sesu");
+        assert_eq!(structure.parse(&buf), Err(WrcError::NoMatchingPacket(buf.len())));
+        buf[..4].copy_from_slice(b"sesp");
+        assert_eq!(structure.parse(&buf).unwrap().id, "session_pause");
+    }
+
+    #[test]
+    fn reject_invalid_structures() {
+        let structure = r#"{ "id": "x", "packets": [{ "id": "session_update", "channels": ["vehicle_tyre_state_fl"] }] }"#;
+        assert_eq!(PacketStructure::from_json(structure, CHANNELS), Err(StructureError::UnknownChannel("vehicle_tyre_state_fl".to_string())));
+        let channels = r#"{ "channels": [{ "id": "vehicle_speed", "type": "half" }] }"#;
+        assert_eq!(PacketStructure::from_json(STRUCTURE, channels), Err(StructureError::UnknownType("half".to_string())));
+        assert!(matches!(PacketStructure::from_json("{", CHANNELS), Err(StructureError::InvalidJson(_))));
+    }
+}
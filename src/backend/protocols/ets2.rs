@@ -0,0 +1,161 @@
+//! JSON served by the community "ETS2 Telemetry Web Server" for Euro Truck Simulator 2 and American Truck Simulator.
+//! The server wraps the SCS telemetry SDK and answers `GET /api/ets2/telemetry` with the latest state of the game.
+//! Only the sections we use are decoded, fields missing from older server versions are left at their defaults.
+//! Speeds are in km/h, pressures in psi and temperatures in celsius.
+
+use serde::Deserialize;
+
+pub const DEFAULT_PORT: u16 = 25555;
+pub const TELEMETRY_PATH: &str = "/api/ets2/telemetry";
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Ets2Game {
+    pub connected: bool,                // False if the game isn't running
+    pub game_name: Option<String>,      // "ETS2" or "ATS", null while not connected
+    pub paused: bool,
+    pub telemetry_plugin_version: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Ets2Placement {
+    pub x: f32,                         // Meters, east
+    pub y: f32,                         // Meters, up
+    pub z: f32,                         // Meters, south
+    pub heading: f32,                   // 0-1 of a full turn, anticlockwise from north
+    pub pitch: f32,                     // -0.25 to 0.25 of a full turn
+    pub roll: f32,                      // -0.5 to 0.5 of a full turn
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Ets2Vector {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Ets2Truck {
+    pub make: String,
+    pub model: String,
+    pub speed: f32,                     // Km/h, negative when reversing
+    pub cruise_control_speed: f32,      // Km/h
+    pub cruise_control_on: bool,
+    pub displayed_gear: i32,            // Negative for reverse gears
+    pub forward_gears: u32,
+    pub engine_rpm: f32,
+    pub engine_rpm_max: f32,
+    pub fuel: f32,                      // Liters
+    pub fuel_capacity: f32,             // Liters
+    pub fuel_warning_on: bool,
+    pub game_throttle: f32,             // 0-1
+    pub game_brake: f32,                // 0-1
+    pub game_clutch: f32,               // 0-1
+    pub engine_on: bool,
+    pub retarder_brake: u32,            // Current retarder step, 0 when off
+    pub retarder_step_count: u32,       // 0 if the truck has no retarder
+    pub park_brake_on: bool,
+    pub motor_brake_on: bool,
+    pub air_pressure: f32,              // Psi
+    pub air_pressure_warning_on: bool,
+    pub air_pressure_emergency_on: bool,
+    pub oil_temperature: f32,
+    pub oil_pressure: f32,              // Psi
+    pub oil_pressure_warning_on: bool,
+    pub water_temperature: f32,
+    pub battery_voltage_warning_on: bool,
+    pub blinker_left_on: bool,          // The lamp state, which blinks
+    pub blinker_right_on: bool,
+    pub lights_parking_on: bool,
+    pub lights_beam_low_on: bool,
+    pub lights_beam_high_on: bool,
+    pub placement: Ets2Placement,
+    pub acceleration: Ets2Vector,       // M/S^2 in truck space, x right, y up, z backwards
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Ets2Navigation {
+    pub estimated_distance: f32,        // Meters to the destination, 0 without a route
+    pub speed_limit: f32,               // Km/h, 0 if there is none
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DataEts2 {
+    pub game: Ets2Game,
+    pub truck: Ets2Truck,
+    pub navigation: Ets2Navigation,
+}
+
+impl DataEts2 {
+    pub fn parse(json: &str) -> Result<Self, Ets2Error> {
+        serde_json::from_str(json).map_err(|e| Ets2Error::InvalidJson(e.to_string()))
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding the telemetry server's response
+pub enum Ets2Error {
+    /// response isn't the JSON we expect
+    InvalidJson(String),
+}
+
+impl std::fmt::Display for Ets2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Trimmed down response of the telemetry server while driving
+    pub const DRIVING: &str = r#"{
+        "game": { "connected": true, "gameName": "ATS", "paused": false, "time": "0001-01-05T14:35:00Z", "timeScale": 20.0, "telemetryPluginVersion": "4", "version": "1.10" },
+        "truck": {
+            "id": "peterbilt.389", "make": "Peterbilt", "model": "389", "speed": 88.2, "cruiseControlSpeed": 88.0, "cruiseControlOn": true,
+            "odometer": 10234.5, "gear": 11, "displayedGear": 11, "forwardGears": 12, "reverseGears": 2, "shifterType": "automatic",
+            "engineRpm": 1380.5, "engineRpmMax": 2100.0, "fuel": 410.0, "fuelCapacity": 820.0, "fuelWarningOn": false,
+            "gameThrottle": 0.42, "gameBrake": 0.0, "gameClutch": 0.0, "engineOn": true, "electricOn": true,
+            "retarderBrake": 1, "retarderStepCount": 3, "parkBrakeOn": false, "motorBrakeOn": false,
+            "airPressure": 120.5, "airPressureWarningOn": false, "airPressureEmergencyOn": false,
+            "oilTemperature": 95.3, "oilPressure": 58.0, "oilPressureWarningOn": false, "waterTemperature": 88.1,
+            "batteryVoltageWarningOn": false, "blinkerLeftActive": true, "blinkerLeftOn": true, "blinkerRightOn": false,
+            "lightsParkingOn": true, "lightsBeamLowOn": true, "lightsBeamHighOn": false,
+            "placement": { "x": -38211.2, "y": 41.7, "z": 12034.9, "heading": 0.25, "pitch": 0.01, "roll": 0.0 },
+            "acceleration": { "x": 0.5, "y": 0.0, "z": -1.0 }
+        },
+        "trailer": { "attached": true, "name": "reefer", "mass": 18200.0 },
+        "navigation": { "estimatedTime": "0001-01-01T02:12:00Z", "estimatedDistance": 184320, "speedLimit": 104 }
+    }"#;
+
+    #[test]
+    fn parse_driving() {
+        let raw = DataEts2::parse(DRIVING).unwrap();
+        assert!(raw.game.connected);
+        assert_eq!(raw.game.game_name.as_deref(), Some("ATS"));
+        assert_eq!(raw.truck.make, "Peterbilt");
+        assert_eq!(raw.truck.displayed_gear, 11);
+        assert_eq!(raw.truck.engine_rpm_max, 2100.0);
+        assert!(raw.truck.cruise_control_on);
+        assert_eq!(raw.truck.retarder_brake, 1);
+        assert_eq!(raw.truck.air_pressure, 120.5);
+        assert!(raw.truck.blinker_left_on);
+        assert_eq!(raw.truck.placement.heading, 0.25);
+        assert_eq!(raw.navigation.estimated_distance, 184320.0);
+    }
+
+    #[test]
+    fn parse_disconnected() {
+        // The server still answers while the game isn't running
+        let raw = DataEts2::parse(r#"{ "game": { "connected": false, "gameName": null }, "truck": {}, "navigation": {} }"#).unwrap();
+        assert!(!raw.game.connected);
+        assert_eq!(raw.truck, Ets2Truck::default());
+        assert!(matches!(DataEts2::parse("<html>"), Err(Ets2Error::InvalidJson(_))));
+    }
+}
@@ -0,0 +1,469 @@
+//! Codemasters/EA F1 UDP telemetry, formats 2023 and 2024.
+//! Every packet starts with the same header, followed by a packet specific body that usually holds data for all 22 cars.
+//! Only the packets we map into `Telemetry` are decoded, everything else is reported as `F1Packet::Other`.
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 20777;
+pub const HEADER_SIZE: usize = 29;
+pub const NUM_CARS: usize = 22;
+
+pub const PACKET_ID_MOTION: u8 = 0;
+pub const PACKET_ID_SESSION: u8 = 1;
+pub const PACKET_ID_LAP_DATA: u8 = 2;
+pub const PACKET_ID_CAR_TELEMETRY: u8 = 6;
+pub const PACKET_ID_CAR_STATUS: u8 = 7;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PacketHeader {
+    pub packet_format: u16,     // 2023, 2024
+    pub game_year: u8,
+    pub game_major_version: u8,
+    pub game_minor_version: u8,
+    pub packet_version: u8,
+    pub packet_id: u8,
+    pub session_uid: u64,
+    pub session_time: f32,      // Seconds
+    pub frame_identifier: u32,
+    pub overall_frame_identifier: u32,
+    pub player_car_index: u8,
+    pub secondary_player_car_index: u8, // 255 if there is no second player
+}
+
+impl PacketHeader {
+    /// Decodes and validates just the header, which is enough to tell whether a packet comes from a supported F1 game.
+    pub fn parse(buf: &[u8]) -> Result<Self, F1Error> {
+        let mut r = ByteReader::new(buf);
+        let header = Self::read(&mut r).ok_or(F1Error::TooShort(buf.len()))?;
+        if !matches!(header.packet_format, 2023 | 2024) {
+            return Err(F1Error::UnsupportedFormat(header.packet_format));
+        }
+        if header.player_car_index as usize >= NUM_CARS {
+            return Err(F1Error::Malformed);
+        }
+        Ok(header)
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            packet_format: r.u16()?,
+            game_year: r.u8()?,
+            game_major_version: r.u8()?,
+            game_minor_version: r.u8()?,
+            packet_version: r.u8()?,
+            packet_id: r.u8()?,
+            session_uid: r.u64()?,
+            session_time: r.f32()?,
+            frame_identifier: r.u32()?,
+            overall_frame_identifier: r.u32()?,
+            player_car_index: r.u8()?,
+            secondary_player_car_index: r.u8()?,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CarMotionData {
+    pub world_position: [f32; 3],   // Meters, x right, y up, z forward
+    pub world_velocity: [f32; 3],   // M/S
+    pub world_forward_dir: [i16; 3], // Normalised, divide by 32767
+    pub world_right_dir: [i16; 3],  // Normalised, divide by 32767
+    pub g_force_lateral: f32,
+    pub g_force_longitudinal: f32,
+    pub g_force_vertical: f32,
+    pub yaw: f32,                   // Radians
+    pub pitch: f32,                 // Radians
+    pub roll: f32,                  // Radians
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PacketSessionData {
+    pub weather: u8,
+    pub track_temperature: i8,      // Celsius
+    pub air_temperature: i8,        // Celsius
+    pub total_laps: u8,
+    pub track_length: u16,          // Meters
+    pub session_type: u8,           // Meaning differs between 2023 and 2024
+    pub track_id: i8,
+    pub formula: u8,
+    pub session_time_left: u16,     // Seconds
+    pub session_duration: u16,      // Seconds
+    pub pit_speed_limit: u8,        // Km/h
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LapData {
+    pub last_lap_time_ms: u32,
+    pub current_lap_time_ms: u32,
+    pub delta_to_car_in_front_ms: u32,
+    pub delta_to_race_leader_ms: u32,
+    pub lap_distance: f32,          // Meters, may be negative before crossing the start line
+    pub total_distance: f32,        // Meters
+    pub car_position: u8,
+    pub current_lap_num: u8,
+    pub pit_status: u8,             // 0 = none, 1 = pitting, 2 = in pit area
+    pub sector: u8,                 // 0 = sector 1, etc
+    pub current_lap_invalid: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CarTelemetryData {
+    pub speed: u16,                 // Km/h
+    pub throttle: f32,              // 0-1
+    pub steer: f32,                 // -1 (full left) to 1 (full right)
+    pub brake: f32,                 // 0-1
+    pub clutch: u8,                 // 0-100
+    pub gear: i8,                   // -1 = reverse, 0 = neutral, 1 = first, etc
+    pub engine_rpm: u16,
+    pub drs: bool,
+    pub rev_lights_percent: u8,     // 0-100
+    pub rev_lights_bit_value: u16,  // Bit 0 is the leftmost LED
+    pub brakes_temperature: [u16; 4],       // Celsius, rear left, rear right, front left, front right
+    pub tyres_surface_temperature: [u8; 4], // Celsius, same order as the brakes
+    pub tyres_inner_temperature: [u8; 4],   // Celsius, same order as the brakes
+    pub engine_temperature: u16,    // Celsius
+    pub tyres_pressure: [f32; 4],   // PSI, same order as the brakes
+    pub surface_type: [u8; 4],
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CarStatusData {
+    pub traction_control: u8,       // 0 = off, 1 = medium, 2 = full
+    pub anti_lock_brakes: bool,
+    pub fuel_mix: u8,
+    pub front_brake_bias: u8,       // Percentage
+    pub pit_limiter_status: bool,
+    pub fuel_in_tank: f32,          // Kg
+    pub fuel_capacity: f32,         // Kg
+    pub fuel_remaining_laps: f32,
+    pub max_rpm: u16,
+    pub idle_rpm: u16,
+    pub max_gears: u8,
+    pub drs_allowed: bool,
+    pub drs_activation_distance: u16, // Meters, 0 if DRS is not available
+    pub ers_store_energy: f32,      // Joules
+    pub ers_deploy_mode: u8,        // 0 = none, 1 = medium, 2 = hotlap, 3 = overtake
+    pub ers_harvested_this_lap_mguk: f32, // Joules
+    pub ers_harvested_this_lap_mguh: f32, // Joules
+    pub ers_deployed_this_lap: f32, // Joules
+    pub network_paused: bool,
+}
+
+/// Joules the ERS store can hold, which is also the per-lap deployment limit
+pub const ERS_MAX_ENERGY: f32 = 4_000_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum F1Packet {
+    Motion(Vec<CarMotionData>),
+    Session(PacketSessionData),
+    LapData(Vec<LapData>),
+    CarTelemetry(Vec<CarTelemetryData>),
+    CarStatus(Vec<CarStatusData>),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataF1 {
+    pub header: PacketHeader,
+    pub packet: F1Packet,
+}
+
+impl DataF1 {
+    /// Decodes a single F1 packet.
+    /// The whole datagram must be passed in, so the size can be checked against the packet ID.
+    pub fn parse(buf: &[u8]) -> Result<Self, F1Error> {
+        let header = PacketHeader::parse(buf)?;
+        let expected = match Self::packet_size(header.packet_format, header.packet_id) {
+            Some(expected) => expected,
+            None => return Ok(Self { header, packet: F1Packet::Other }),
+        };
+        if buf.len() < expected {
+            return Err(F1Error::TooShort(buf.len()));
+        } else if buf.len() > expected {
+            return Err(F1Error::UnexpectedLength(buf.len()));
+        }
+
+        let mut r = ByteReader::new(&buf[HEADER_SIZE..]);
+        let format = header.packet_format;
+        let packet = match header.packet_id {
+            PACKET_ID_MOTION => Self::read_cars(&mut r, Self::read_motion).map(F1Packet::Motion),
+            PACKET_ID_SESSION => Self::read_session(&mut r).map(F1Packet::Session),
+            PACKET_ID_LAP_DATA => Self::read_cars(&mut r, |r| Self::read_lap_data(r, format)).map(F1Packet::LapData),
+            PACKET_ID_CAR_TELEMETRY => Self::read_cars(&mut r, Self::read_car_telemetry).map(F1Packet::CarTelemetry),
+            PACKET_ID_CAR_STATUS => Self::read_cars(&mut r, Self::read_car_status).map(F1Packet::CarStatus),
+            _ => unreachable!(),
+        }.ok_or(F1Error::TooShort(buf.len()))?;
+
+        Ok(Self {
+            header,
+            packet,
+        })
+    }
+
+    /// Size of the packets we decode, None for the ones we don't
+    pub fn packet_size(format: u16, packet_id: u8) -> Option<usize> {
+        match (format, packet_id) {
+            (_, PACKET_ID_MOTION) => Some(1349),
+            (2023, PACKET_ID_SESSION) => Some(644),
+            (_, PACKET_ID_SESSION) => Some(753),
+            (2023, PACKET_ID_LAP_DATA) => Some(1131),
+            (_, PACKET_ID_LAP_DATA) => Some(1285),
+            (_, PACKET_ID_CAR_TELEMETRY) => Some(1352),
+            (_, PACKET_ID_CAR_STATUS) => Some(1239),
+            _ => None,
+        }
+    }
+
+    fn read_cars<T>(r: &mut ByteReader, read_car: impl Fn(&mut ByteReader) -> Option<T>) -> Option<Vec<T>> {
+        (0..NUM_CARS).map(|_| read_car(r)).collect()
+    }
+
+    fn read_motion(r: &mut ByteReader) -> Option<CarMotionData> {
+        Some(CarMotionData {
+            world_position: [r.f32()?, r.f32()?, r.f32()?],
+            world_velocity: [r.f32()?, r.f32()?, r.f32()?],
+            world_forward_dir: [r.i16()?, r.i16()?, r.i16()?],
+            world_right_dir: [r.i16()?, r.i16()?, r.i16()?],
+            g_force_lateral: r.f32()?,
+            g_force_longitudinal: r.f32()?,
+            g_force_vertical: r.f32()?,
+            yaw: r.f32()?,
+            pitch: r.f32()?,
+            roll: r.f32()?,
+        })
+    }
+
+    fn read_session(r: &mut ByteReader) -> Option<PacketSessionData> {
+        Some(PacketSessionData {
+            weather: r.u8()?,
+            track_temperature: r.i8()?,
+            air_temperature: r.i8()?,
+            total_laps: r.u8()?,
+            track_length: r.u16()?,
+            session_type: r.u8()?,
+            track_id: r.i8()?,
+            formula: r.u8()?,
+            session_time_left: r.u16()?,
+            session_duration: r.u16()?,
+            pit_speed_limit: r.u8()?,
+        })
+    }
+
+    fn read_lap_data(r: &mut ByteReader, format: u16) -> Option<LapData> {
+        let last_lap_time_ms = r.u32()?;
+        let current_lap_time_ms = r.u32()?;
+        r.skip(6)?; // Sector times
+        // 2024 split the deltas into a milliseconds and a minutes part
+        let (delta_to_car_in_front_ms, delta_to_race_leader_ms) = if format == 2023 {
+            (r.u16()? as u32, r.u16()? as u32)
+        } else {
+            let front = r.u16()? as u32 + r.u8()? as u32 * 60_000;
+            let leader = r.u16()? as u32 + r.u8()? as u32 * 60_000;
+            (front, leader)
+        };
+        let lap_distance = r.f32()?;
+        let total_distance = r.f32()?;
+        r.skip(4)?; // Safety car delta
+        let car_position = r.u8()?;
+        let current_lap_num = r.u8()?;
+        let pit_status = r.u8()?;
+        r.skip(1)?; // Number of pit stops
+        let sector = r.u8()?;
+        let current_lap_invalid = r.u8()? != 0;
+        // Penalties, warnings, grid position, statuses and pit timers
+        r.skip(if format == 2023 { 14 } else { 19 })?;
+        Some(LapData {
+            last_lap_time_ms,
+            current_lap_time_ms,
+            delta_to_car_in_front_ms,
+            delta_to_race_leader_ms,
+            lap_distance,
+            total_distance,
+            car_position,
+            current_lap_num,
+            pit_status,
+            sector,
+            current_lap_invalid,
+        })
+    }
+
+    fn read_car_telemetry(r: &mut ByteReader) -> Option<CarTelemetryData> {
+        Some(CarTelemetryData {
+            speed: r.u16()?,
+            throttle: r.f32()?,
+            steer: r.f32()?,
+            brake: r.f32()?,
+            clutch: r.u8()?,
+            gear: r.i8()?,
+            engine_rpm: r.u16()?,
+            drs: r.u8()? != 0,
+            rev_lights_percent: r.u8()?,
+            rev_lights_bit_value: r.u16()?,
+            brakes_temperature: [r.u16()?, r.u16()?, r.u16()?, r.u16()?],
+            tyres_surface_temperature: r.bytes()?,
+            tyres_inner_temperature: r.bytes()?,
+            engine_temperature: r.u16()?,
+            tyres_pressure: [r.f32()?, r.f32()?, r.f32()?, r.f32()?],
+            surface_type: r.bytes()?,
+        })
+    }
+
+    fn read_car_status(r: &mut ByteReader) -> Option<CarStatusData> {
+        let status = CarStatusData {
+            traction_control: r.u8()?,
+            anti_lock_brakes: r.u8()? != 0,
+            fuel_mix: r.u8()?,
+            front_brake_bias: r.u8()?,
+            pit_limiter_status: r.u8()? != 0,
+            fuel_in_tank: r.f32()?,
+            fuel_capacity: r.f32()?,
+            fuel_remaining_laps: r.f32()?,
+            max_rpm: r.u16()?,
+            idle_rpm: r.u16()?,
+            max_gears: r.u8()?,
+            drs_allowed: r.u8()? != 0,
+            drs_activation_distance: r.u16()?,
+            ..Default::default()
+        };
+        r.skip(12)?; // Tyre compounds, tyre age, FIA flags and ICE/MGU-K power
+        Some(CarStatusData {
+            ers_store_energy: r.f32()?,
+            ers_deploy_mode: r.u8()?,
+            ers_harvested_this_lap_mguk: r.f32()?,
+            ers_harvested_this_lap_mguh: r.f32()?,
+            ers_deployed_this_lap: r.f32()?,
+            network_paused: r.u8()? != 0,
+            ..status
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an f1 packet
+pub enum F1Error {
+    /// packet is shorter than its header or packet id requires
+    TooShort(usize),
+    /// packet is longer than its packet id requires
+    UnexpectedLength(usize),
+    /// packet format (game year) we can't decode
+    UnsupportedFormat(u16),
+    /// packet header contains values that make no sense
+    Malformed,
+}
+
+impl std::fmt::Display for F1Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(format: u16, packet_id: u8, player_car_index: u8) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&format.to_le_bytes());
+        buf.extend_from_slice(&[(format % 100) as u8, 1, 5, 1, packet_id]);
+        buf.extend_from_slice(&0x1234_5678_9abc_def0u64.to_le_bytes());
+        buf.extend_from_slice(&12.5f32.to_le_bytes());
+        buf.extend_from_slice(&100u32.to_le_bytes());
+        buf.extend_from_slice(&100u32.to_le_bytes());
+        buf.extend_from_slice(&[player_car_index, 255]);
+        buf
+    }
+
+    fn car_telemetry(rpm: u16) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&288u16.to_le_bytes());
+        for v in [1.0f32, -0.25, 0.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0, 7]);
+        buf.extend_from_slice(&rpm.to_le_bytes());
+        buf.extend_from_slice(&[1, 85]);
+        buf.extend_from_slice(&0x3ffu16.to_le_bytes());
+        for t in [500u16, 510, 600, 610] {
+            buf.extend_from_slice(&t.to_le_bytes());
+        }
+        buf.extend_from_slice(&[95, 96, 97, 98, 100, 101, 102, 103]);
+        buf.extend_from_slice(&110u16.to_le_bytes());
+        for p in [21.5f32, 21.5, 23.0, 23.0] {
+            buf.extend_from_slice(&p.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0; 4]);
+        buf
+    }
+
+    #[test]
+    fn parse_car_telemetry() {
+        let mut buf = header(2024, PACKET_ID_CAR_TELEMETRY, 3);
+        for i in 0..NUM_CARS {
+            buf.extend_from_slice(&car_telemetry(10_000 + i as u16));
+        }
+        buf.extend_from_slice(&[0, 255, 0]);
+
+        let raw = DataF1::parse(&buf).unwrap();
+        assert_eq!(raw.header.packet_format, 2024);
+        assert_eq!(raw.header.player_car_index, 3);
+        let F1Packet::CarTelemetry(cars) = raw.packet else { panic!("expected car telemetry") };
+        let car = &cars[3];
+        assert_eq!(car.engine_rpm, 10_003);
+        assert_eq!(car.speed, 288);
+        assert_eq!(car.steer, -0.25);
+        assert_eq!(car.gear, 7);
+        assert!(car.drs);
+        assert_eq!(car.rev_lights_percent, 85);
+        assert_eq!(car.brakes_temperature, [500, 510, 600, 610]);
+        assert_eq!(car.tyres_surface_temperature, [95, 96, 97, 98]);
+        assert_eq!(car.engine_temperature, 110);
+        assert_eq!(car.tyres_pressure[2], 23.0);
+    }
+
+    #[test]
+    fn parse_lap_data_2024() {
+        let mut buf = header(2024, PACKET_ID_LAP_DATA, 0);
+        for _ in 0..NUM_CARS {
+            buf.extend_from_slice(&91_234u32.to_le_bytes());
+            buf.extend_from_slice(&45_000u32.to_le_bytes());
+            buf.extend_from_slice(&[0; 6]);
+            buf.extend_from_slice(&1_500u16.to_le_bytes());
+            buf.push(0);
+            buf.extend_from_slice(&500u16.to_le_bytes());
+            buf.push(1);
+            buf.extend_from_slice(&2_100.5f32.to_le_bytes());
+            buf.extend_from_slice(&30_000.0f32.to_le_bytes());
+            buf.extend_from_slice(&0.0f32.to_le_bytes());
+            buf.extend_from_slice(&[4, 6, 0, 1, 2, 0]);
+            buf.extend_from_slice(&[0; 19]);
+        }
+        buf.extend_from_slice(&[255, 255]);
+
+        let F1Packet::LapData(cars) = DataF1::parse(&buf).unwrap().packet else { panic!("expected lap data") };
+        let lap = &cars[0];
+        assert_eq!(lap.last_lap_time_ms, 91_234);
+        assert_eq!(lap.delta_to_car_in_front_ms, 1_500);
+        assert_eq!(lap.delta_to_race_leader_ms, 60_500);
+        assert_eq!(lap.lap_distance, 2_100.5);
+        assert_eq!(lap.car_position, 4);
+        assert_eq!(lap.current_lap_num, 6);
+        assert_eq!(lap.sector, 2);
+    }
+
+    #[test]
+    fn unknown_packets_are_passed_over() {
+        let mut buf = header(2023, 3, 0);
+        buf.extend_from_slice(b"SSTA");
+        assert_eq!(DataF1::parse(&buf).unwrap().packet, F1Packet::Other);
+    }
+
+    #[test]
+    fn reject_invalid_packets() {
+        assert_eq!(PacketHeader::parse(&header(2022, 0, 0)), Err(F1Error::UnsupportedFormat(2022)));
+        assert_eq!(PacketHeader::parse(&header(2023, 0, 30)), Err(F1Error::Malformed));
+        assert_eq!(PacketHeader::parse(&header(2023, 0, 0)[..20]), Err(F1Error::TooShort(20)));
+        let mut buf = header(2023, PACKET_ID_CAR_STATUS, 0);
+        buf.extend_from_slice(&[0; 100]);
+        assert_eq!(DataF1::parse(&buf), Err(F1Error::TooShort(129)));
+    }
+}
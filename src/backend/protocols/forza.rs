@@ -0,0 +1,267 @@
+//! Forza "Data Out", as sent by Forza Motorsport 7/2023 and Forza Horizon 4/5.
+//! The "Sled" format is 232 bytes. The "Dash" format appends dashboard data: directly after the sled in Motorsport (311 bytes),
+//! or after 12 undocumented bytes in Horizon (324 bytes). Forza Motorsport (2023) adds tyre wear and the track to the dash (331 bytes).
+
+use super::ByteReader;
+
+pub const SLED_SIZE: usize = 232;
+pub const DASH_SIZE: usize = 311;
+pub const HORIZON_DASH_SIZE: usize = 324;
+pub const MOTORSPORT_2023_DASH_SIZE: usize = 331;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ForzaSled {
+    pub is_race_on: bool,               // False in menus, all other values are zero then
+    pub timestamp_ms: u32,
+    pub engine_max_rpm: f32,
+    pub engine_idle_rpm: f32,
+    pub current_engine_rpm: f32,
+    pub acceleration: [f32; 3],         // M/S^2 in car space, x right, y up, z forward
+    pub velocity: [f32; 3],             // M/S in car space, x right, y up, z forward
+    pub angular_velocity: [f32; 3],     // Radians per second, pitch, yaw, roll
+    pub yaw: f32,                       // Radians
+    pub pitch: f32,                     // Radians
+    pub roll: f32,                      // Radians
+    pub normalized_suspension_travel: [f32; 4], // 0 = max stretch, 1 = max compression. Front left, front right, rear left, rear right
+    pub tire_slip_ratio: [f32; 4],      // 0 = 100% grip, |ratio| > 1 = loss of grip
+    pub wheel_rotation_speed: [f32; 4], // Radians per second
+    pub wheel_on_rumble_strip: [i32; 4],
+    pub wheel_in_puddle_depth: [f32; 4], // 0-1
+    pub surface_rumble: [f32; 4],
+    pub tire_slip_angle: [f32; 4],      // 0 = 100% grip, |angle| > 1 = loss of grip
+    pub tire_combined_slip: [f32; 4],   // 0 = 100% grip, |slip| > 1 = loss of grip
+    pub suspension_travel_meters: [f32; 4],
+    pub car_ordinal: i32,
+    pub car_class: i32,                 // 0 (D) to 7 (X)
+    pub car_performance_index: i32,     // 100-999
+    pub drivetrain_type: i32,           // 0 = FWD, 1 = RWD, 2 = AWD
+    pub num_cylinders: i32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ForzaDash {
+    pub position: [f32; 3],             // Meters in world space, x right, y up, z forward
+    pub speed: f32,                     // M/S
+    pub power: f32,                     // Watts
+    pub torque: f32,                    // Newton meter
+    pub tire_temp: [f32; 4],            // Fahrenheit, front left, front right, rear left, rear right
+    pub boost: f32,                     // PSI
+    pub fuel: f32,                      // 0-1
+    pub distance_traveled: f32,         // Meters
+    pub best_lap: f32,                  // Seconds
+    pub last_lap: f32,                  // Seconds
+    pub current_lap: f32,               // Seconds
+    pub current_race_time: f32,         // Seconds
+    pub lap_number: u16,                // Starting at 0
+    pub race_position: u8,              // Starting at 1, 0 outside of races
+    pub accel: u8,                      // 0-255
+    pub brake: u8,                      // 0-255
+    pub clutch: u8,                     // 0-255
+    pub hand_brake: u8,                 // 0-255
+    pub gear: u8,                       // Reverse = 0, first = 1, etc
+    pub steer: i8,                      // -127 (left) to 127 (right)
+    pub normalized_driving_line: i8,
+    pub normalized_ai_brake_difference: i8,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataForza {
+    pub sled: ForzaSled,
+    pub dash: Option<ForzaDash>,        // None for the sled format
+}
+
+impl DataForza {
+    /// Decodes a single Data Out packet, telling the formats apart by their size.
+    pub fn parse(buf: &[u8]) -> Result<Self, ForzaError> {
+        let dash_offset = match buf.len() {
+            SLED_SIZE => None,
+            DASH_SIZE | MOTORSPORT_2023_DASH_SIZE => Some(SLED_SIZE),
+            HORIZON_DASH_SIZE => Some(SLED_SIZE + 12),
+            n if n < SLED_SIZE => return Err(ForzaError::TooShort(n)),
+            n => return Err(ForzaError::UnexpectedLength(n)),
+        };
+
+        let sled = Self::read_sled(&mut ByteReader::new(buf)).ok_or(ForzaError::TooShort(buf.len()))?;
+        let dash = match dash_offset {
+            Some(offset) => Some(Self::read_dash(&mut ByteReader::new(&buf[offset..])).ok_or(ForzaError::TooShort(buf.len()))?),
+            None => None,
+        };
+
+        if !sled.current_engine_rpm.is_finite() || !sled.engine_max_rpm.is_finite() {
+            return Err(ForzaError::Malformed);
+        }
+
+        Ok(Self {
+            sled,
+            dash,
+        })
+    }
+
+    fn read_sled(r: &mut ByteReader) -> Option<ForzaSled> {
+        Some(ForzaSled {
+            is_race_on: r.i32()? != 0,
+            timestamp_ms: r.u32()?,
+            engine_max_rpm: r.f32()?,
+            engine_idle_rpm: r.f32()?,
+            current_engine_rpm: r.f32()?,
+            acceleration: [r.f32()?, r.f32()?, r.f32()?],
+            velocity: [r.f32()?, r.f32()?, r.f32()?],
+            angular_velocity: [r.f32()?, r.f32()?, r.f32()?],
+            yaw: r.f32()?,
+            pitch: r.f32()?,
+            roll: r.f32()?,
+            normalized_suspension_travel: Self::read_f32x4(r)?,
+            tire_slip_ratio: Self::read_f32x4(r)?,
+            wheel_rotation_speed: Self::read_f32x4(r)?,
+            wheel_on_rumble_strip: [r.i32()?, r.i32()?, r.i32()?, r.i32()?],
+            wheel_in_puddle_depth: Self::read_f32x4(r)?,
+            surface_rumble: Self::read_f32x4(r)?,
+            tire_slip_angle: Self::read_f32x4(r)?,
+            tire_combined_slip: Self::read_f32x4(r)?,
+            suspension_travel_meters: Self::read_f32x4(r)?,
+            car_ordinal: r.i32()?,
+            car_class: r.i32()?,
+            car_performance_index: r.i32()?,
+            drivetrain_type: r.i32()?,
+            num_cylinders: r.i32()?,
+        })
+    }
+
+    fn read_dash(r: &mut ByteReader) -> Option<ForzaDash> {
+        Some(ForzaDash {
+            position: [r.f32()?, r.f32()?, r.f32()?],
+            speed: r.f32()?,
+            power: r.f32()?,
+            torque: r.f32()?,
+            tire_temp: Self::read_f32x4(r)?,
+            boost: r.f32()?,
+            fuel: r.f32()?,
+            distance_traveled: r.f32()?,
+            best_lap: r.f32()?,
+            last_lap: r.f32()?,
+            current_lap: r.f32()?,
+            current_race_time: r.f32()?,
+            lap_number: r.u16()?,
+            race_position: r.u8()?,
+            accel: r.u8()?,
+            brake: r.u8()?,
+            clutch: r.u8()?,
+            hand_brake: r.u8()?,
+            gear: r.u8()?,
+            steer: r.i8()?,
+            normalized_driving_line: r.i8()?,
+            normalized_ai_brake_difference: r.i8()?,
+        })
+    }
+
+    fn read_f32x4(r: &mut ByteReader) -> Option<[f32; 4]> {
+        Some([r.f32()?, r.f32()?, r.f32()?, r.f32()?])
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding a forza data out packet
+pub enum ForzaError {
+    /// packet is shorter than the sled format
+    TooShort(usize),
+    /// packet length matches none of the known formats
+    UnexpectedLength(usize),
+    /// packet has the right size but contains values that make no sense
+    Malformed,
+}
+
+impl std::fmt::Display for ForzaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sled() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&1i32.to_le_bytes());
+        buf.extend_from_slice(&123_456u32.to_le_bytes());
+        for v in [8_500.0f32, 900.0, 6_250.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        // Motion, then the per wheel arrays, filled with their index so the order can be checked
+        for i in 0..12 {
+            buf.extend_from_slice(&(i as f32).to_le_bytes());
+        }
+        for _ in 0..9 {
+            for wheel in 0..4 {
+                buf.extend_from_slice(&(wheel as f32).to_le_bytes());
+            }
+        }
+        for v in [2_345i32, 5, 801, 1, 6] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf
+    }
+
+    fn dash() -> Vec<u8> {
+        let mut buf = Vec::new();
+        for v in [10.0f32, 2.0, -30.0, 41.5, 250_000.0, 400.0, 180.0, 185.0, 190.0, 195.0, 14.5, 0.75, 1_234.0, 95.1, 96.2, 30.5, 200.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        buf.extend_from_slice(&[3, 255, 0, 0, 0, 4, (-64i8) as u8, 0, 0]);
+        buf
+    }
+
+    #[test]
+    fn parse_sled() {
+        let raw = DataForza::parse(&sled()).unwrap();
+        assert!(raw.sled.is_race_on);
+        assert_eq!(raw.sled.engine_max_rpm, 8_500.0);
+        assert_eq!(raw.sled.current_engine_rpm, 6_250.0);
+        assert_eq!(raw.sled.velocity, [3.0, 4.0, 5.0]);
+        assert_eq!(raw.sled.roll, 11.0);
+        assert_eq!(raw.sled.suspension_travel_meters, [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(raw.sled.car_performance_index, 801);
+        assert_eq!(raw.sled.num_cylinders, 6);
+        assert!(raw.dash.is_none());
+    }
+
+    #[test]
+    fn parse_motorsport_dash() {
+        let mut buf = sled();
+        buf.extend_from_slice(&dash());
+        assert_eq!(buf.len(), DASH_SIZE);
+        let dash = DataForza::parse(&buf).unwrap().dash.unwrap();
+        assert_eq!(dash.position, [10.0, 2.0, -30.0]);
+        assert_eq!(dash.speed, 41.5);
+        assert_eq!(dash.tire_temp, [180.0, 185.0, 190.0, 195.0]);
+        assert_eq!(dash.best_lap, 95.1);
+        assert_eq!(dash.lap_number, 2);
+        assert_eq!(dash.race_position, 3);
+        assert_eq!(dash.accel, 255);
+        assert_eq!(dash.gear, 4);
+        assert_eq!(dash.steer, -64);
+    }
+
+    #[test]
+    fn parse_horizon_dash() {
+        let mut buf = sled();
+        buf.extend_from_slice(&[0xff; 12]);
+        buf.extend_from_slice(&dash());
+        buf.push(0);
+        assert_eq!(buf.len(), HORIZON_DASH_SIZE);
+        let dash = DataForza::parse(&buf).unwrap().dash.unwrap();
+        assert_eq!(dash.speed, 41.5);
+        assert_eq!(dash.fuel, 0.75);
+        assert_eq!(dash.gear, 4);
+    }
+
+    #[test]
+    fn reject_unknown_lengths() {
+        let buf = sled();
+        assert_eq!(DataForza::parse(&buf[..100]), Err(ForzaError::TooShort(100)));
+        let mut buf = buf;
+        buf.extend_from_slice(&[0; 10]);
+        assert_eq!(DataForza::parse(&buf), Err(ForzaError::UnexpectedLength(242)));
+    }
+}
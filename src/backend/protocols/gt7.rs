@@ -0,0 +1,247 @@
+//! Gran Turismo 7 "Simulator Interface" telemetry.
+//! The console only sends packets for a while after receiving a heartbeat, which has to be repeated to keep them coming.
+//! Every packet is encrypted with Salsa20, using a fixed key and a nonce derived from 4 unencrypted bytes at 0x40.
+//! Decrypted packets start with a magic number, which is the only way to tell a packet was decrypted correctly.
+
+use salsa20::{Salsa20, cipher::{KeyIvInit, StreamCipher}};
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 33740;
+pub const HEARTBEAT_PORT: u16 = 33739;
+pub const HEARTBEAT: &[u8] = b"A";
+pub const PACKET_SIZE: usize = 0x128;
+pub const MAGIC: u32 = 0x4737_5330; // "0S7G"
+
+pub const FLAG_ON_TRACK: u16 = 1 << 0;
+pub const FLAG_LOADING: u16 = 1 << 2;
+pub const FLAG_HAS_TURBO: u16 = 1 << 4;
+pub const FLAG_REV_LIMITER_ALERT: u16 = 1 << 5;
+pub const FLAG_HAND_BRAKE: u16 = 1 << 6;
+pub const FLAG_LIGHTS: u16 = 1 << 7;
+pub const FLAG_HIGH_BEAM: u16 = 1 << 8;
+pub const FLAG_LOW_BEAM: u16 = 1 << 9;
+pub const FLAG_TCS: u16 = 1 << 11;
+
+const KEY: &[u8; 32] = b"Simulator Interface Packet GT7 v"; // The first 32 bytes of "Simulator Interface Packet GT7 ver 0.0"
+const IV_OFFSET: usize = 0x40;
+const IV_MASK: u32 = 0xdead_beaf;
+
+/// A decrypted packet. Wheel arrays are front left, front right, rear left, rear right.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataGt7 {
+    pub position: [f32; 3],             // Meters in world space, x right, y up, z forward
+    pub velocity: [f32; 3],             // M/S in world space
+    pub rotation: [f32; 3],             // Orientation quaternion x, y and z
+    pub relative_orientation_to_north: f32, // Orientation quaternion w, 1 facing north, 0 facing south
+    pub angular_velocity: [f32; 3],     // Radians per second
+    pub body_height: f32,               // Meters
+    pub engine_rpm: f32,
+    pub fuel_level: f32,                // Liters, or percent for electric cars
+    pub fuel_capacity: f32,             // Liters, 100 for electric cars
+    pub speed: f32,                     // M/S
+    pub boost: f32,                     // Bar, offset by 1
+    pub oil_pressure: f32,              // Bar
+    pub water_temp: f32,                // Celsius
+    pub oil_temp: f32,                  // Celsius
+    pub tyre_temp: [f32; 4],            // Celsius
+    pub packet_id: i32,
+    pub current_lap: i16,               // Starting at 1, 0 before the start
+    pub total_laps: i16,                // 0 outside of races
+    pub best_lap_time_ms: i32,          // -1 if there is none
+    pub last_lap_time_ms: i32,          // -1 if there is none
+    pub time_of_day_ms: i32,
+    pub race_start_position: i16,       // -1 once the race has started
+    pub num_cars_pre_race: i16,         // -1 once the race has started
+    pub min_alert_rpm: u16,             // Rpm at which the shift lights start
+    pub max_alert_rpm: u16,             // Rpm at which the shift lights flash, the rev limiter
+    pub calculated_max_speed: i16,      // Km/h
+    pub flags: u16,                     // FLAG_*
+    pub gears: u8,                      // Current gear in the low nibble (0 = reverse, 15 = neutral), suggested gear in the high nibble
+    pub throttle: u8,                   // 0-255
+    pub brake: u8,                      // 0-255
+    pub road_plane: [f32; 3],
+    pub road_plane_distance: f32,
+    pub wheel_rps: [f32; 4],            // Radians per second, negative when driving forwards
+    pub tyre_radius: [f32; 4],          // Meters
+    pub suspension_height: [f32; 4],    // Meters
+    pub clutch: f32,                    // 0-1
+    pub clutch_engagement: f32,         // 0-1
+    pub rpm_after_clutch: f32,
+    pub transmission_top_speed: f32,    // As a ratio
+    pub gear_ratios: [f32; 8],
+    pub car_code: i32,
+}
+
+impl DataGt7 {
+    /// Decrypts and decodes a packet as it arrives from the console.
+    pub fn parse(buf: &[u8]) -> Result<Self, Gt7Error> {
+        if buf.len() < PACKET_SIZE {
+            return Err(Gt7Error::TooShort(buf.len()));
+        } else if buf.len() > PACKET_SIZE {
+            return Err(Gt7Error::UnexpectedLength(buf.len()));
+        }
+
+        let decrypted = decrypt(buf);
+        let mut r = ByteReader::new(&decrypted);
+        if r.u32() != Some(MAGIC) {
+            return Err(Gt7Error::BadMagic);
+        }
+        Self::read(&mut r).ok_or(Gt7Error::TooShort(buf.len()))
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            position: r.array(ByteReader::f32)?,
+            velocity: r.array(ByteReader::f32)?,
+            rotation: r.array(ByteReader::f32)?,
+            relative_orientation_to_north: r.f32()?,
+            angular_velocity: r.array(ByteReader::f32)?,
+            body_height: r.f32()?,
+            engine_rpm: r.f32()?,
+            fuel_level: {
+                r.skip(4)?; // Nonce
+                r.f32()?
+            },
+            fuel_capacity: r.f32()?,
+            speed: r.f32()?,
+            boost: r.f32()?,
+            oil_pressure: r.f32()?,
+            water_temp: r.f32()?,
+            oil_temp: r.f32()?,
+            tyre_temp: r.array(ByteReader::f32)?,
+            packet_id: r.i32()?,
+            current_lap: r.i16()?,
+            total_laps: r.i16()?,
+            best_lap_time_ms: r.i32()?,
+            last_lap_time_ms: r.i32()?,
+            time_of_day_ms: r.i32()?,
+            race_start_position: r.i16()?,
+            num_cars_pre_race: r.i16()?,
+            min_alert_rpm: r.u16()?,
+            max_alert_rpm: r.u16()?,
+            calculated_max_speed: r.i16()?,
+            flags: r.u16()?,
+            gears: r.u8()?,
+            throttle: r.u8()?,
+            brake: r.u8()?,
+            road_plane: {
+                r.skip(1)?; // Unused
+                r.array(ByteReader::f32)?
+            },
+            road_plane_distance: r.f32()?,
+            wheel_rps: r.array(ByteReader::f32)?,
+            tyre_radius: r.array(ByteReader::f32)?,
+            suspension_height: r.array(ByteReader::f32)?,
+            clutch: {
+                r.skip(32)?; // Unknown
+                r.f32()?
+            },
+            clutch_engagement: r.f32()?,
+            rpm_after_clutch: r.f32()?,
+            transmission_top_speed: r.f32()?,
+            gear_ratios: r.array(ByteReader::f32)?,
+            car_code: r.i32()?,
+        })
+    }
+
+    /// Current gear, -1 for reverse and 0 for neutral
+    pub fn gear(&self) -> isize {
+        match self.gears & 0x0f {
+            0 => -1,
+            15 => 0,
+            gear => gear as isize,
+        }
+    }
+}
+
+/// Runs a full size packet through Salsa20 with the nonce it carries. Encrypting and decrypting are the same operation.
+fn decrypt(buf: &[u8]) -> Vec<u8> {
+    let iv = u32::from_le_bytes([buf[IV_OFFSET], buf[IV_OFFSET + 1], buf[IV_OFFSET + 2], buf[IV_OFFSET + 3]]);
+    let mut nonce = [0u8; 8];
+    nonce[..4].copy_from_slice(&(iv ^ IV_MASK).to_le_bytes());
+    nonce[4..].copy_from_slice(&iv.to_le_bytes());
+
+    let mut out = buf.to_vec();
+    Salsa20::new(KEY.into(), &nonce.into()).apply_keystream(&mut out);
+    out
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding a GT7 packet
+pub enum Gt7Error {
+    /// packet is shorter than a GT7 packet
+    TooShort(usize),
+    /// packet is longer than a GT7 packet
+    UnexpectedLength(usize),
+    /// packet doesn't start with the magic number after decrypting it, so it isn't from GT7
+    BadMagic,
+}
+
+impl std::fmt::Display for Gt7Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Builds a plain packet with some recognisable values, then encrypts it like the console does
+    pub fn encrypted_packet(rpm: f32, iv: u32) -> Vec<u8> {
+        let mut buf = vec![0u8; PACKET_SIZE];
+        let mut put = |offset: usize, bytes: &[u8]| buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(0x00, &MAGIC.to_le_bytes());
+        put(0x04, &[10.0f32, 2.0, -30.0].map(f32::to_le_bytes).concat());
+        put(0x3c, &rpm.to_le_bytes());
+        put(0x44, &[30.0f32, 60.0, 41.0, 1.8].map(f32::to_le_bytes).concat());
+        put(0x60, &[80.0f32, 81.0, 70.0, 71.0].map(f32::to_le_bytes).concat());
+        put(0x74, &[3i16.to_le_bytes(), 5i16.to_le_bytes()].concat());
+        put(0x78, &[95_123i32.to_le_bytes(), (-1i32).to_le_bytes()].concat());
+        put(0x88, &[6500u16.to_le_bytes(), 7800u16.to_le_bytes()].concat());
+        put(0x8e, &(FLAG_ON_TRACK | FLAG_HAS_TURBO | FLAG_LOW_BEAM).to_le_bytes());
+        put(0x90, &[0x34, 255, 0]);
+        put(0xa4, &[-80.0f32, -80.0, -81.0, -81.0].map(f32::to_le_bytes).concat());
+        put(0xb4, &[0.3f32; 4].map(f32::to_le_bytes).concat());
+        put(0x124, &3447i32.to_le_bytes());
+
+        // The nonce is taken from the encrypted packet, so it has to be in place before encrypting
+        buf[IV_OFFSET..IV_OFFSET + 4].copy_from_slice(&iv.to_le_bytes());
+        let mut encrypted = decrypt(&buf);
+        encrypted[IV_OFFSET..IV_OFFSET + 4].copy_from_slice(&iv.to_le_bytes());
+        encrypted
+    }
+
+    #[test]
+    fn decrypt_packet() {
+        let buf = encrypted_packet(7012.5, 0x1234_5678);
+        assert_ne!(buf[..4], MAGIC.to_le_bytes());
+        let raw = DataGt7::parse(&buf).unwrap();
+        assert_eq!(raw.position, [10.0, 2.0, -30.0]);
+        assert_eq!(raw.engine_rpm, 7012.5);
+        assert_eq!(raw.fuel_level, 30.0);
+        assert_eq!(raw.fuel_capacity, 60.0);
+        assert_eq!(raw.boost, 1.8);
+        assert_eq!(raw.tyre_temp, [80.0, 81.0, 70.0, 71.0]);
+        assert_eq!((raw.current_lap, raw.total_laps), (3, 5));
+        assert_eq!(raw.best_lap_time_ms, 95_123);
+        assert_eq!(raw.last_lap_time_ms, -1);
+        assert_eq!((raw.min_alert_rpm, raw.max_alert_rpm), (6500, 7800));
+        assert_eq!(raw.gear(), 4);
+        assert_eq!(raw.throttle, 255);
+        assert_eq!(raw.tyre_radius, [0.3; 4]);
+        assert_eq!(raw.car_code, 3447);
+    }
+
+    #[test]
+    fn reject_invalid_packets() {
+        let mut buf = encrypted_packet(1000.0, 7);
+        // A different nonce decrypts into garbage
+        buf[IV_OFFSET] ^= 1;
+        assert_eq!(DataGt7::parse(&buf), Err(Gt7Error::BadMagic));
+        assert_eq!(DataGt7::parse(&buf[..100]), Err(Gt7Error::TooShort(100)));
+        buf.push(0);
+        assert_eq!(DataGt7::parse(&buf), Err(Gt7Error::UnexpectedLength(PACKET_SIZE + 1)));
+    }
+}
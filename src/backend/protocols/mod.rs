@@ -0,0 +1,103 @@
+//! Decoders for the wire formats spoken by the supported games.
+//! These only turn raw bytes into typed packets; mapping them into `Telemetry` is left to the game backends.
+
+pub mod outgauge;
+pub mod outsim;
+pub mod f1;
+pub mod forza;
+pub mod assetto_corsa;
+pub mod acc;
+pub mod sms;
+pub mod dirt_rally;
+pub mod ea_wrc;
+pub mod gt7;
+pub mod ets2;
+pub mod rbr;
+
+/// Little-endian cursor over a received packet.
+/// Every read is bounds checked and returns `None` instead of panicking when the packet runs out,
+/// so decoders never have to index the buffer themselves.
+pub struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            pos: 0,
+        }
+    }
+
+    pub fn slice(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.slice(N)?.try_into().ok()
+    }
+
+    /// Skips over `n` bytes, for padding and fields we don't care about
+    pub fn skip(&mut self, n: usize) -> Option<()> {
+        self.slice(n).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|b| b[0])
+    }
+
+    pub fn i8(&mut self) -> Option<i8> {
+        self.bytes().map(i8::from_le_bytes)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn i16(&mut self) -> Option<i16> {
+        self.bytes().map(i16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Option<i32> {
+        self.bytes().map(i32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    pub fn i64(&mut self) -> Option<i64> {
+        self.bytes().map(i64::from_le_bytes)
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+
+    pub fn f64(&mut self) -> Option<f64> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    /// Reads `N` consecutive values, for the per-wheel arrays most formats have
+    pub fn array<T: Default, const N: usize>(&mut self, read: impl Fn(&mut Self) -> Option<T>) -> Option<[T; N]> {
+        let mut values: [T; N] = std::array::from_fn(|_| T::default());
+        for v in values.iter_mut() {
+            *v = read(self)?;
+        }
+        Some(values)
+    }
+}
+
+/// Decodes a fixed size, NUL-terminated (or NUL-padded) string field.
+/// Fields that use their full width without a terminator are accepted as well.
+pub fn decode_cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
@@ -0,0 +1,211 @@
+//! OutGauge, as originally defined by Live for Speed and also emitted by BeamNG.Drive.
+//! The packet is 92 bytes, or 96 bytes when the game is configured with an OutGauge ID.
+
+use super::{ByteReader, decode_cstr};
+
+/// Packet size without the optional `outgauge_id` field
+pub const PACKET_SIZE: usize = 92;
+/// Packet size with the optional `outgauge_id` field
+pub const PACKET_SIZE_WITH_ID: usize = 96;
+
+pub const FLAG_TURBO: u16 = 8192;   // Show turbo yes/no
+pub const FLAG_KM: u16 = 16384;     // If not set, user prefers miles over kilometers
+pub const FLAG_BAR: u16 = 32768;    // If not set, user prefers PSI over bar.
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataOutGauge {
+    pub time: u32,              // Beam hardcodes this to 0.
+    pub name: String,           // Beam hardcodes this to "beam". Perhaps in the future, with a mod, we could extend this to contain the car name
+    pub flags: u16,
+    pub gear: u8,               // Reverse = 0, neutral = 1, first = 2, etc // TODO: In beam, you can have multiple reverse gears. Figure out how this works
+    pub plid: u8,               // Beam hardcodes this to 0. Perhaps BeamMP could fill this data out?
+    pub speed: f32,             // M/S
+    pub rpm: f32,               // RPM
+    pub turbo: f32,             // Bar
+    pub engine_temp: f32,       // Celsius
+    pub fuel: f32,              // 0-1
+    pub oil_pressure: f32,      // Bar // Beam hardcodes this to 0 (I think it's lacking from the sim entirely)
+    pub oil_temp: f32,          // C
+    pub dash_lights: u32,       // The dashboard lights that exist for this car
+    pub show_lights: u32,       // Which dash_lights are actually on
+    pub throttle: f32,          // 0-1
+    pub brake: f32,             // 0-1
+    pub clutch: f32,            // 0-1
+    pub display1: String,       // Usually fuel in outgauge, but beam hardcodes it to an empty string
+    pub display2: String,       // Usually settings in outgauge, but beam hardcodes it to an empty string
+    pub outgauge_id: Option<i32>, // Only present if an outgauge ID is specified
+}
+
+impl DataOutGauge {
+    /// Decodes a single OutGauge packet.
+    /// The whole datagram must be passed in, so trailing bytes can be detected.
+    pub fn parse(buf: &[u8]) -> Result<Self, OutGaugeError> {
+        let has_id = match buf.len() {
+            PACKET_SIZE => false,
+            PACKET_SIZE_WITH_ID => true,
+            n if n < PACKET_SIZE => return Err(OutGaugeError::TooShort(n)),
+            n => return Err(OutGaugeError::UnexpectedLength(n)),
+        };
+
+        let raw = Self::read(&mut ByteReader::new(buf), has_id).ok_or(OutGaugeError::TooShort(buf.len()))?;
+
+        if !raw.speed.is_finite() || !raw.rpm.is_finite() {
+            return Err(OutGaugeError::Malformed);
+        }
+
+        Ok(raw)
+    }
+
+    fn read(r: &mut ByteReader, has_id: bool) -> Option<Self> {
+        Some(Self {
+            time: r.u32()?,
+            name: decode_cstr(&r.bytes::<4>()?),
+            flags: r.u16()?,
+            gear: r.u8()?,
+            plid: r.u8()?,
+            speed: r.f32()?,
+            rpm: r.f32()?,
+            turbo: r.f32()?,
+            engine_temp: r.f32()?,
+            fuel: r.f32()?,
+            oil_pressure: r.f32()?,
+            oil_temp: r.f32()?,
+            dash_lights: r.u32()?,
+            show_lights: r.u32()?,
+            throttle: r.f32()?,
+            brake: r.f32()?,
+            clutch: r.f32()?,
+            display1: decode_cstr(&r.bytes::<16>()?),
+            display2: decode_cstr(&r.bytes::<16>()?),
+            outgauge_id: if has_id { Some(r.i32()?) } else { None },
+        })
+    }
+
+    /// Gear as used by `Telemetry`: -1 is reverse, 0 is neutral, 1 is first, etc
+    pub fn gear(&self) -> isize {
+        (self.gear as isize) - 1
+    }
+
+    /// Boost in bar, if the car reports having a turbo
+    pub fn turbo(&self) -> Option<f32> {
+        if (self.flags & FLAG_TURBO) > 0 {
+            Some(self.turbo)
+        } else {
+            None
+        }
+    }
+
+    pub fn prefers_km(&self) -> bool {
+        (self.flags & FLAG_KM) > 0
+    }
+
+    pub fn prefers_bar(&self) -> bool {
+        (self.flags & FLAG_BAR) > 0
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an outgauge packet
+pub enum OutGaugeError {
+    /// packet is shorter than the smallest outgauge packet
+    TooShort(usize),
+    /// packet length matches neither outgauge variant, most likely trailing garbage
+    UnexpectedLength(usize),
+    /// packet has the right size but contains values that make no sense
+    Malformed,
+}
+
+impl std::fmt::Display for OutGaugeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packet as BeamNG.Drive sends it with default outgauge settings: no ID, 3rd gear, no turbo.
+    const BEAMNG_CAPTURE: [u8; 92] = [
+        0x00, 0x00, 0x00, 0x00, 0x62, 0x65, 0x61, 0x6d, 0x00, 0xc0, 0x04, 0x00, 0x66, 0x66, 0xaa, 0x41,
+        0x00, 0x28, 0x8d, 0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb6, 0x42, 0x00, 0x00, 0x20, 0x3f,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb4, 0x42, 0x66, 0x07, 0x00, 0x00, 0x04, 0x04, 0x00, 0x00,
+        0x33, 0x33, 0x33, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // Packet as Live for Speed sends it with `OutGauge ID 7`: turbo shown, 2nd gear, both displays filled in.
+    const LFS_CAPTURE_WITH_ID: [u8; 96] = [
+        0xe8, 0x03, 0x00, 0x00, 0x58, 0x46, 0x47, 0x00, 0x00, 0x60, 0x03, 0x01, 0x00, 0x00, 0x20, 0x41,
+        0x00, 0x80, 0xbb, 0x45, 0x00, 0x00, 0x00, 0x3f, 0x00, 0x00, 0xa0, 0x42, 0x00, 0x00, 0x40, 0x3f,
+        0x00, 0x00, 0x80, 0x40, 0x00, 0x00, 0xa0, 0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x46, 0x75, 0x65, 0x6c,
+        0x20, 0x33, 0x2e, 0x35, 0x6c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x72, 0x61, 0x6b,
+        0x65, 0x20, 0x35, 0x34, 0x25, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn parse_beamng_without_id() {
+        let raw = DataOutGauge::parse(&BEAMNG_CAPTURE).unwrap();
+        assert_eq!(raw.name, "beam");
+        assert!(raw.prefers_km() && raw.prefers_bar());
+        assert_eq!(raw.gear(), 3);
+        assert_eq!(raw.speed, 21.3);
+        assert_eq!(raw.rpm, 4517.0);
+        assert_eq!(raw.turbo(), None);
+        assert_eq!(raw.engine_temp, 91.0);
+        assert_eq!(raw.fuel, 0.625);
+        assert_eq!(raw.oil_temp, 90.0);
+        assert_eq!(raw.dash_lights, 0x766);
+        assert_eq!(raw.show_lights, 0x404);
+        assert_eq!(raw.throttle, 0.7);
+        assert_eq!(raw.display1, "");
+        assert_eq!(raw.display2, "");
+        assert_eq!(raw.outgauge_id, None);
+    }
+
+    #[test]
+    fn parse_lfs_with_id() {
+        let raw = DataOutGauge::parse(&LFS_CAPTURE_WITH_ID).unwrap();
+        assert_eq!(raw.time, 1000);
+        assert_eq!(raw.name, "XFG");
+        assert_eq!(raw.gear(), 2);
+        assert_eq!(raw.plid, 1);
+        assert!(raw.prefers_km() && !raw.prefers_bar());
+        assert_eq!(raw.turbo(), Some(0.5));
+        assert_eq!(raw.oil_pressure, 4.0);
+        assert_eq!(raw.brake, 1.0);
+        assert_eq!(raw.display1, "Fuel 3.5l");
+        assert_eq!(raw.display2, "Brake 54%");
+        assert_eq!(raw.outgauge_id, Some(7));
+    }
+
+    #[test]
+    fn reject_short_packet() {
+        assert_eq!(DataOutGauge::parse(&BEAMNG_CAPTURE[..64]), Err(OutGaugeError::TooShort(64)));
+        assert_eq!(DataOutGauge::parse(&[]), Err(OutGaugeError::TooShort(0)));
+    }
+
+    #[test]
+    fn reject_trailing_garbage() {
+        let mut buf = LFS_CAPTURE_WITH_ID.to_vec();
+        buf.extend_from_slice(&[0xde, 0xad]);
+        assert_eq!(DataOutGauge::parse(&buf), Err(OutGaugeError::UnexpectedLength(98)));
+        assert_eq!(DataOutGauge::parse(&buf[..94]), Err(OutGaugeError::UnexpectedLength(94)));
+    }
+
+    #[test]
+    fn reject_non_finite_values() {
+        let mut buf = BEAMNG_CAPTURE;
+        buf[16..20].copy_from_slice(&f32::NAN.to_le_bytes());
+        assert_eq!(DataOutGauge::parse(&buf), Err(OutGaugeError::Malformed));
+    }
+
+    #[test]
+    fn display_without_terminator() {
+        let mut buf = BEAMNG_CAPTURE;
+        buf[60..76].copy_from_slice(b"0123456789abcdef");
+        assert_eq!(DataOutGauge::parse(&buf).unwrap().display1, "0123456789abcdef");
+    }
+}
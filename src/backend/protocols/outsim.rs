@@ -0,0 +1,342 @@
+//! OutSim, as defined by Live for Speed and also emitted by BeamNG.Drive.
+//! Without `OutSim Opts` (and in BeamNG) this is a fixed 64 byte packet, or 68 bytes with an OutSim ID.
+//! With `OutSim Opts` set, LFS only sends the blocks enabled by the option bits, in a fixed order.
+
+use super::{ByteReader, decode_cstr};
+
+/// Legacy packet size without the optional ID
+pub const PACKET_SIZE: usize = 64;
+/// Legacy packet size with the optional ID
+pub const PACKET_SIZE_WITH_ID: usize = 68;
+
+pub const OSO_HEADER: u16 = 1;
+pub const OSO_ID: u16 = 2;
+pub const OSO_TIME: u16 = 4;
+pub const OSO_MAIN: u16 = 8;
+pub const OSO_INPUTS: u16 = 16;
+pub const OSO_DRIVE: u16 = 32;
+pub const OSO_DISTANCE: u16 = 64;
+pub const OSO_WHEELS: u16 = 128;
+pub const OSO_EXTRA_1: u16 = 256;
+
+const HEADER: &[u8] = b"LFST";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutSimMain {
+    pub ang_vel: [f32; 3],  // Radians per second, around the world x, y and z axes
+    pub heading: f32,       // Radians, anticlockwise from above (Z)
+    pub pitch: f32,         // Radians, anticlockwise from right (X)
+    pub roll: f32,          // Radians, anticlockwise from front (Y)
+    pub accel: [f32; 3],    // M/S^2, world x, y and z
+    pub vel: [f32; 3],      // M/S, world x, y and z
+    pub pos: [i32; 3],      // 1m = 65536
+}
+
+impl OutSimMain {
+    /// World position in meters
+    pub fn position(&self) -> [f64; 3] {
+        self.pos.map(|p| p as f64 / 65536.0)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutSimInputs {
+    pub throttle: f32,      // 0-1
+    pub brake: f32,         // 0-1
+    pub input_steer: f32,   // Radians
+    pub clutch: f32,        // 0-1
+    pub handbrake: f32,     // 0-1
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutSimDrive {
+    pub gear: u8,               // Reverse = 0, neutral = 1, first = 2, etc
+    pub engine_ang_vel: f32,    // Radians per second
+    pub max_torque_at_vel: f32, // Radians per second
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutSimDistance {
+    pub current_lap_dist: f32,  // Meters travelled by the car this lap
+    pub indexed_distance: f32,  // Meters along the track path
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutSimWheel {
+    pub susp_deflect: f32,          // Meters, compression from unloaded
+    pub steer: f32,                 // Radians, including Ackermann and toe
+    pub x_force: f32,               // Newton, force right
+    pub y_force: f32,               // Newton, force forward
+    pub vertical_load: f32,         // Newton, perpendicular to the road
+    pub ang_vel: f32,               // Radians per second
+    pub lean_rel_to_road: f32,      // Radians, anticlockwise seen from the rear
+    pub air_temp: u8,               // Celsius
+    pub slip_fraction: u8,          // 0-255, fraction of the contact patch that is slipping
+    pub touching: bool,
+    pub slip_ratio: f32,
+    pub tangent_slip_angle: f32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataOutSim {
+    pub id: Option<i32>,
+    pub time: Option<u32>,                  // Milliseconds
+    pub main: Option<OutSimMain>,
+    pub inputs: Option<OutSimInputs>,
+    pub drive: Option<OutSimDrive>,
+    pub distance: Option<OutSimDistance>,
+    pub wheels: Option<[OutSimWheel; 4]>,   // Rear left, rear right, front left, front right
+    pub extra_flags: Option<u32>,
+}
+
+impl DataOutSim {
+    /// Decodes a single OutSim packet.
+    /// `opts` must match the `OutSim Opts` value the game is configured with; 0 selects the legacy layout.
+    pub fn parse(buf: &[u8], opts: u16) -> Result<Self, OutSimError> {
+        if opts == 0 {
+            return Self::parse_legacy(buf);
+        }
+
+        let expected = Self::packet_size(opts);
+        if buf.len() < expected {
+            return Err(OutSimError::TooShort(buf.len()));
+        } else if buf.len() > expected {
+            return Err(OutSimError::UnexpectedLength(buf.len()));
+        }
+
+        if (opts & OSO_HEADER) > 0 && &buf[..4] != HEADER {
+            return Err(OutSimError::BadHeader(decode_cstr(&buf[..4])));
+        }
+
+        let raw = Self::read(&mut ByteReader::new(buf), opts).ok_or(OutSimError::TooShort(buf.len()))?;
+        raw.validate()?;
+        Ok(raw)
+    }
+
+    fn parse_legacy(buf: &[u8]) -> Result<Self, OutSimError> {
+        let has_id = match buf.len() {
+            PACKET_SIZE => false,
+            PACKET_SIZE_WITH_ID => true,
+            n if n < PACKET_SIZE => return Err(OutSimError::TooShort(n)),
+            n => return Err(OutSimError::UnexpectedLength(n)),
+        };
+
+        let raw = Self::read_legacy(&mut ByteReader::new(buf), has_id).ok_or(OutSimError::TooShort(buf.len()))?;
+        raw.validate()?;
+        Ok(raw)
+    }
+
+    /// Size of a packet sent with the given `OutSim Opts`
+    pub fn packet_size(opts: u16) -> usize {
+        [
+            (OSO_HEADER, 4),
+            (OSO_ID, 4),
+            (OSO_TIME, 4),
+            (OSO_MAIN, 60),
+            (OSO_INPUTS, 20),
+            (OSO_DRIVE, 12),
+            (OSO_DISTANCE, 8),
+            (OSO_WHEELS, 4 * 40),
+            (OSO_EXTRA_1, 4),
+        ].iter().filter(|(flag, _)| (opts & flag) > 0).map(|(_, size)| size).sum()
+    }
+
+    fn read_legacy(r: &mut ByteReader, has_id: bool) -> Option<Self> {
+        Some(Self {
+            time: Some(r.u32()?),
+            main: Some(Self::read_main(r)?),
+            id: if has_id { Some(r.i32()?) } else { None },
+            ..Default::default()
+        })
+    }
+
+    fn read(r: &mut ByteReader, opts: u16) -> Option<Self> {
+        let has = |flag: u16| (opts & flag) > 0;
+        if has(OSO_HEADER) {
+            r.bytes::<4>()?;
+        }
+        Some(Self {
+            id: if has(OSO_ID) { Some(r.i32()?) } else { None },
+            time: if has(OSO_TIME) { Some(r.u32()?) } else { None },
+            main: if has(OSO_MAIN) { Some(Self::read_main(r)?) } else { None },
+            inputs: if has(OSO_INPUTS) {
+                Some(OutSimInputs {
+                    throttle: r.f32()?,
+                    brake: r.f32()?,
+                    input_steer: r.f32()?,
+                    clutch: r.f32()?,
+                    handbrake: r.f32()?,
+                })
+            } else { None },
+            drive: if has(OSO_DRIVE) {
+                let gear = r.u8()?;
+                r.bytes::<3>()?; // Spare
+                Some(OutSimDrive {
+                    gear,
+                    engine_ang_vel: r.f32()?,
+                    max_torque_at_vel: r.f32()?,
+                })
+            } else { None },
+            distance: if has(OSO_DISTANCE) {
+                Some(OutSimDistance {
+                    current_lap_dist: r.f32()?,
+                    indexed_distance: r.f32()?,
+                })
+            } else { None },
+            wheels: if has(OSO_WHEELS) {
+                Some([Self::read_wheel(r)?, Self::read_wheel(r)?, Self::read_wheel(r)?, Self::read_wheel(r)?])
+            } else { None },
+            extra_flags: if has(OSO_EXTRA_1) { Some(r.u32()?) } else { None },
+        })
+    }
+
+    fn read_main(r: &mut ByteReader) -> Option<OutSimMain> {
+        Some(OutSimMain {
+            ang_vel: [r.f32()?, r.f32()?, r.f32()?],
+            heading: r.f32()?,
+            pitch: r.f32()?,
+            roll: r.f32()?,
+            accel: [r.f32()?, r.f32()?, r.f32()?],
+            vel: [r.f32()?, r.f32()?, r.f32()?],
+            pos: [r.i32()?, r.i32()?, r.i32()?],
+        })
+    }
+
+    fn read_wheel(r: &mut ByteReader) -> Option<OutSimWheel> {
+        let wheel = OutSimWheel {
+            susp_deflect: r.f32()?,
+            steer: r.f32()?,
+            x_force: r.f32()?,
+            y_force: r.f32()?,
+            vertical_load: r.f32()?,
+            ang_vel: r.f32()?,
+            lean_rel_to_road: r.f32()?,
+            air_temp: r.u8()?,
+            slip_fraction: r.u8()?,
+            touching: r.u8()? != 0,
+            ..Default::default()
+        };
+        r.u8()?; // Spare
+        Some(OutSimWheel {
+            slip_ratio: r.f32()?,
+            tangent_slip_angle: r.f32()?,
+            ..wheel
+        })
+    }
+
+    fn validate(&self) -> Result<(), OutSimError> {
+        if let Some(main) = &self.main {
+            let mut values = main.ang_vel.iter().chain(&main.accel).chain(&main.vel).chain([&main.heading, &main.pitch, &main.roll]);
+            if values.any(|v| !v.is_finite()) {
+                return Err(OutSimError::Malformed);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an outsim packet
+pub enum OutSimError {
+    /// packet is shorter than the configured outsim layout
+    TooShort(usize),
+    /// packet is longer than the configured outsim layout, most likely trailing garbage or mismatched opts
+    UnexpectedLength(usize),
+    /// packet does not start with the "LFST" header
+    BadHeader(String),
+    /// packet has the right size but contains values that make no sense
+    Malformed,
+}
+
+impl std::fmt::Display for OutSimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Legacy packet as BeamNG.Drive sends it: accelerating and turning, 100m east, 50m south and 3.5m up.
+    const LEGACY_PACKET: [u8; 64] = [
+        0x88, 0x13, 0x00, 0x00, 0xcd, 0xcc, 0xcc, 0x3d, 0x00, 0x00, 0x00, 0x00, 0xcd, 0xcc, 0x4c, 0xbe,
+        0x00, 0x00, 0xc0, 0x3f, 0x0a, 0xd7, 0xa3, 0x3c, 0x0a, 0xd7, 0x23, 0xbc, 0x00, 0x00, 0x00, 0x3f,
+        0x00, 0x00, 0x10, 0x41, 0xc3, 0xf5, 0x1c, 0xc1, 0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0xa0, 0x41,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00, 0xce, 0xff, 0x00, 0x80, 0x03, 0x00,
+    ];
+
+    fn wheel_bytes(susp_deflect: f32, air_temp: u8) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for v in [susp_deflect, 0.1, 200.0, -150.0, 3500.0, 40.0, 0.01] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&[air_temp, 32, 1, 0]);
+        buf.extend_from_slice(&0.05f32.to_le_bytes());
+        buf.extend_from_slice(&0.02f32.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn parse_legacy() {
+        let raw = DataOutSim::parse(&LEGACY_PACKET, 0).unwrap();
+        assert_eq!(raw.time, Some(5000));
+        assert_eq!(raw.id, None);
+        let main = raw.main.unwrap();
+        assert_eq!(main.ang_vel, [0.1, 0.0, -0.2]);
+        assert_eq!(main.heading, 1.5);
+        assert_eq!(main.accel, [0.5, 9.0, -9.81]);
+        assert_eq!(main.vel, [1.0, 20.0, 0.0]);
+        assert_eq!(main.position(), [100.0, -50.0, 3.5]);
+        assert!(raw.wheels.is_none());
+    }
+
+    #[test]
+    fn parse_legacy_with_id() {
+        let mut buf = LEGACY_PACKET.to_vec();
+        buf.extend_from_slice(&3i32.to_le_bytes());
+        assert_eq!(DataOutSim::parse(&buf, 0).unwrap().id, Some(3));
+    }
+
+    #[test]
+    fn parse_with_opts() {
+        let opts = OSO_HEADER | OSO_TIME | OSO_MAIN | OSO_DRIVE | OSO_WHEELS;
+        let mut buf = b"LFST".to_vec();
+        buf.extend_from_slice(&LEGACY_PACKET);
+        buf.extend_from_slice(&[3, 0, 0, 0]);
+        buf.extend_from_slice(&600.0f32.to_le_bytes());
+        buf.extend_from_slice(&550.0f32.to_le_bytes());
+        for (i, air_temp) in [70, 71, 80, 81].into_iter().enumerate() {
+            buf.extend_from_slice(&wheel_bytes(i as f32 * 0.01, air_temp));
+        }
+        assert_eq!(buf.len(), DataOutSim::packet_size(opts));
+
+        let raw = DataOutSim::parse(&buf, opts).unwrap();
+        assert_eq!(raw.time, Some(5000));
+        assert_eq!(raw.main.unwrap().heading, 1.5);
+        assert_eq!(raw.drive.unwrap().engine_ang_vel, 600.0);
+        assert!(raw.inputs.is_none());
+        let wheels = raw.wheels.unwrap();
+        assert_eq!(wheels[3].susp_deflect, 0.03);
+        assert_eq!(wheels[2].air_temp, 80);
+        assert!(wheels[0].touching);
+        assert_eq!(wheels[1].tangent_slip_angle, 0.02);
+    }
+
+    #[test]
+    fn reject_mismatched_opts() {
+        let opts = OSO_HEADER | OSO_TIME | OSO_MAIN;
+        let mut buf = b"LFSX".to_vec();
+        buf.extend_from_slice(&LEGACY_PACKET);
+        assert_eq!(DataOutSim::parse(&buf, opts), Err(OutSimError::BadHeader("LFSX".to_string())));
+        assert_eq!(DataOutSim::parse(&buf, opts | OSO_ID), Err(OutSimError::TooShort(68)));
+        assert_eq!(DataOutSim::parse(&buf, OSO_MAIN), Err(OutSimError::UnexpectedLength(68)));
+    }
+
+    #[test]
+    fn reject_non_finite_values() {
+        let mut buf = LEGACY_PACKET;
+        buf[28..32].copy_from_slice(&f32::INFINITY.to_le_bytes());
+        assert_eq!(DataOutSim::parse(&buf, 0), Err(OutSimError::Malformed));
+    }
+}
@@ -0,0 +1,286 @@
+//! Richard Burns Rally telemetry, as sent by the RSF (NGP) plugin once UDP telemetry is enabled in `RichardBurnsRally.ini`.
+//! Every packet is one packed `TelemetryData` struct: stage progress, the driver's controls and the car, which carries
+//! a full suspension, brake and tyre model for each of its four corners.
+//! Temperatures are in kelvin, angles in degrees and car space motion is surge (forward), sway (right) and heave (up).
+
+use super::ByteReader;
+
+pub const DEFAULT_PORT: u16 = 6776;
+pub const PACKET_SIZE: usize = 664;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrStage {
+    pub index: i32,
+    pub progress: f32,                  // Distance along the stage, 0-1
+    pub race_time: f32,                 // Seconds since the start
+    pub drive_line_location: f32,       // Meters driven along the drive line
+    pub distance_to_end: f32,           // Meters
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrControl {
+    pub steering: f32,                  // -1 (left) to 1 (right)
+    pub throttle: f32,                  // 0-1
+    pub brake: f32,                     // 0-1
+    pub handbrake: f32,                 // 0-1
+    pub clutch: f32,                    // 0-1
+    pub gear: i32,                      // 0 = reverse, 1 = neutral, 2 = first gear
+    pub footbrake_pressure: f32,        // Bar
+    pub handbrake_pressure: f32,        // Bar
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrMotion {
+    pub surge: f32,                     // M/S or M/S^2
+    pub sway: f32,
+    pub heave: f32,
+    pub roll: f32,                      // Degrees per second or per second squared
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrEngine {
+    pub rpm: f32,
+    pub radiator_coolant_temperature: f32, // Kelvin
+    pub engine_coolant_temperature: f32,   // Kelvin
+    pub engine_temperature: f32,           // Kelvin
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrTyreSegment {
+    pub temperature: f32,               // Kelvin
+    pub wear: f32,                      // 0-1
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrTyre {
+    pub pressure: f32,                  // Kilopascal
+    pub temperature: f32,               // Kelvin
+    pub carcass_temperature: f32,       // Kelvin
+    pub tread_temperature: f32,         // Kelvin
+    pub current_segment: u32,           // The segment touching the road
+    pub segments: [RbrTyreSegment; 8],
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrBrakeDisk {
+    pub layer_temperature: f32,         // Kelvin
+    pub temperature: f32,               // Kelvin
+    pub wear: f32,                      // 0-1
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrDamper {
+    pub damage: f32,                    // 0-1
+    pub piston_velocity: f32,           // M/S
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrSuspension {
+    pub spring_deflection: f32,         // Meters, positive is compression
+    pub rollbar_force: f32,             // Newton
+    pub spring_force: f32,              // Newton
+    pub damper_force: f32,              // Newton
+    pub strut_force: f32,               // Newton, the load carried by the corner
+    pub helper_spring_is_active: bool,
+    pub damper: RbrDamper,
+    pub brake_disk: RbrBrakeDisk,
+    pub tyre: RbrTyre,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RbrCar {
+    pub index: i32,
+    pub speed: f32,                     // Km/h
+    pub position: [f32; 3],             // Meters in world space, x right, y forward, z up
+    pub roll: f32,                      // Degrees
+    pub pitch: f32,                     // Degrees
+    pub yaw: f32,                       // Degrees, clockwise from north
+    pub velocities: RbrMotion,          // In car space
+    pub accelerations: RbrMotion,       // In car space
+    pub engine: RbrEngine,
+    pub suspension: [RbrSuspension; 4], // Front left, front right, rear left, rear right
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataRbr {
+    pub total_steps: u32,               // Physics steps since the game started, increases with every packet
+    pub stage: RbrStage,
+    pub control: RbrControl,
+    pub car: RbrCar,
+}
+
+impl DataRbr {
+    pub fn parse(buf: &[u8]) -> Result<Self, RbrError> {
+        if buf.len() < PACKET_SIZE {
+            return Err(RbrError::TooShort(buf.len()));
+        } else if buf.len() > PACKET_SIZE {
+            return Err(RbrError::UnexpectedLength(buf.len()));
+        }
+        Self::read(&mut ByteReader::new(buf)).ok_or(RbrError::TooShort(buf.len()))
+    }
+
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            total_steps: r.u32()?,
+            stage: RbrStage {
+                index: r.i32()?,
+                progress: r.f32()?,
+                race_time: r.f32()?,
+                drive_line_location: r.f32()?,
+                distance_to_end: r.f32()?,
+            },
+            control: RbrControl {
+                steering: r.f32()?,
+                throttle: r.f32()?,
+                brake: r.f32()?,
+                handbrake: r.f32()?,
+                clutch: r.f32()?,
+                gear: r.i32()?,
+                footbrake_pressure: r.f32()?,
+                handbrake_pressure: r.f32()?,
+            },
+            car: RbrCar {
+                index: r.i32()?,
+                speed: r.f32()?,
+                position: r.array(ByteReader::f32)?,
+                roll: r.f32()?,
+                pitch: r.f32()?,
+                yaw: r.f32()?,
+                velocities: read_motion(r)?,
+                accelerations: read_motion(r)?,
+                engine: RbrEngine {
+                    rpm: r.f32()?,
+                    radiator_coolant_temperature: r.f32()?,
+                    engine_coolant_temperature: r.f32()?,
+                    engine_temperature: r.f32()?,
+                },
+                suspension: r.array(read_suspension)?,
+            },
+        })
+    }
+
+    /// Current gear, -1 for reverse and 0 for neutral
+    pub fn gear(&self) -> isize {
+        self.control.gear as isize - 1
+    }
+}
+
+fn read_motion(r: &mut ByteReader) -> Option<RbrMotion> {
+    Some(RbrMotion {
+        surge: r.f32()?,
+        sway: r.f32()?,
+        heave: r.f32()?,
+        roll: r.f32()?,
+        pitch: r.f32()?,
+        yaw: r.f32()?,
+    })
+}
+
+fn read_suspension(r: &mut ByteReader) -> Option<RbrSuspension> {
+    Some(RbrSuspension {
+        spring_deflection: r.f32()?,
+        rollbar_force: r.f32()?,
+        spring_force: r.f32()?,
+        damper_force: r.f32()?,
+        strut_force: r.f32()?,
+        helper_spring_is_active: r.i32()? != 0,
+        damper: RbrDamper {
+            damage: r.f32()?,
+            piston_velocity: r.f32()?,
+        },
+        brake_disk: RbrBrakeDisk {
+            layer_temperature: r.f32()?,
+            temperature: r.f32()?,
+            wear: r.f32()?,
+        },
+        tyre: RbrTyre {
+            pressure: r.f32()?,
+            temperature: r.f32()?,
+            carcass_temperature: r.f32()?,
+            tread_temperature: r.f32()?,
+            current_segment: r.u32()?,
+            segments: r.array(|r| Some(RbrTyreSegment {
+                temperature: r.f32()?,
+                wear: r.f32()?,
+            }))?,
+        },
+    })
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an RBR packet
+pub enum RbrError {
+    /// packet is shorter than the telemetry struct
+    TooShort(usize),
+    /// packet is longer than the telemetry struct
+    UnexpectedLength(usize),
+}
+
+impl std::fmt::Display for RbrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Offset of the first corner's suspension, each corner takes 128 bytes
+    const SUSPENSION_OFFSET: usize = 152;
+
+    /// Builds a packet from a car on stage with some recognisable values
+    pub fn on_stage(rpm: f32, gear: i32) -> Vec<u8> {
+        let mut buf = vec![0u8; PACKET_SIZE];
+        let mut put = |offset: usize, bytes: &[u8]| buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(0, &1234u32.to_le_bytes());
+        put(4, &[7i32.to_le_bytes(), 0.25f32.to_le_bytes(), 95.5f32.to_le_bytes(), 2500.0f32.to_le_bytes(), 7500.0f32.to_le_bytes()].concat());
+        put(24, &[0.1f32, 0.8, 0.0, 0.0, 0.0].map(f32::to_le_bytes).concat());
+        put(44, &gear.to_le_bytes());
+        put(60, &[108.0f32, 100.0, 200.0, 5.0, 0.0, 2.0, 90.0].map(f32::to_le_bytes).concat());
+        put(88, &[30.0f32, 0.0, 0.0].map(f32::to_le_bytes).concat());
+        put(136, &[rpm, 353.15, 363.15, 373.15].map(f32::to_le_bytes).concat());
+        for corner in 0..4 {
+            let base = SUSPENSION_OFFSET + corner * 128;
+            put(base, &[0.05f32, 0.06, 0.07, 0.08][corner].to_le_bytes());
+            put(base + 16, &3500.0f32.to_le_bytes());
+            put(base + 36, &573.15f32.to_le_bytes());
+            put(base + 44, &[200.0f32, 343.15].map(f32::to_le_bytes).concat());
+            put(base + 60, &3u32.to_le_bytes());
+            put(base + 128 - 8, &[330.0f32, 0.5].map(f32::to_le_bytes).concat());
+        }
+        buf
+    }
+
+    #[test]
+    fn parse_on_stage() {
+        let raw = DataRbr::parse(&on_stage(6100.0, 4)).unwrap();
+        assert_eq!(raw.total_steps, 1234);
+        assert_eq!(raw.stage.index, 7);
+        assert_eq!(raw.stage.distance_to_end, 7500.0);
+        assert_eq!(raw.control.throttle, 0.8);
+        assert_eq!(raw.gear(), 3);
+        assert_eq!(raw.car.speed, 108.0);
+        assert_eq!(raw.car.position, [100.0, 200.0, 5.0]);
+        assert_eq!(raw.car.yaw, 90.0);
+        assert_eq!(raw.car.velocities.surge, 30.0);
+        assert_eq!(raw.car.engine.rpm, 6100.0);
+        assert_eq!(raw.car.engine.engine_coolant_temperature, 363.15);
+        let rear_right = &raw.car.suspension[3];
+        assert_eq!(rear_right.spring_deflection, 0.08);
+        assert_eq!(rear_right.strut_force, 3500.0);
+        assert_eq!(rear_right.brake_disk.temperature, 573.15);
+        assert_eq!(rear_right.tyre.pressure, 200.0);
+        assert_eq!(rear_right.tyre.current_segment, 3);
+        assert_eq!(rear_right.tyre.segments[7], RbrTyreSegment { temperature: 330.0, wear: 0.5 });
+    }
+
+    #[test]
+    fn reject_invalid_packets() {
+        assert_eq!(DataRbr::parse(&[0; 100]), Err(RbrError::TooShort(100)));
+        assert_eq!(DataRbr::parse(&[0; PACKET_SIZE + 1]), Err(RbrError::UnexpectedLength(PACKET_SIZE + 1)));
+        assert_eq!(DataRbr::parse(&[0; PACKET_SIZE]).unwrap().gear(), -1);
+    }
+}
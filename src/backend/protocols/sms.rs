@@ -0,0 +1,652 @@
+//! Slightly Mad Studios UDP, protocol version 2 ("patch 5"), as sent by Project CARS 2 and Automobilista 2.
+//! The game broadcasts several packet types at their own rates, each starting with the same 12 byte base.
+//! Data for all participants is spread over packets: participant names come 16 at a time, together with the indices they belong to.
+//! Packets may carry trailing padding, so only a minimum size is enforced.
+
+use super::{ByteReader, decode_cstr};
+
+pub const DEFAULT_PORT: u16 = 5606;
+pub const MAX_PARTICIPANTS: usize = 32;
+pub const PARTICIPANTS_PER_PACKET: usize = 16;
+
+pub const PACKET_TYPE_TELEMETRY: u8 = 0;
+pub const PACKET_TYPE_RACE_DEFINITION: u8 = 1;
+pub const PACKET_TYPE_PARTICIPANTS: u8 = 2;
+pub const PACKET_TYPE_TIMINGS: u8 = 3;
+pub const PACKET_TYPE_GAME_STATE: u8 = 4;
+pub const PACKET_TYPE_TIME_STATS: u8 = 7;
+
+pub const CAR_HEADLIGHT: u8 = 1 << 0;
+pub const CAR_ENGINE_WARNING: u8 = 1 << 2;
+pub const CAR_SPEED_LIMITER: u8 = 1 << 3;
+pub const CAR_ABS: u8 = 1 << 4;
+pub const CAR_HANDBRAKE: u8 = 1 << 5;
+
+const TYRE_NAME_LENGTH: usize = 40;
+const STRING_LENGTH: usize = 64;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PacketBase {
+    pub packet_number: u32,             // Counter over all packets
+    pub category_packet_number: u32,    // Counter over packets of this type
+    pub partial_packet_index: u8,       // Starting at 1
+    pub partial_packet_number: u8,      // Number of packets this data is split over
+    pub packet_type: u8,
+    pub packet_version: u8,
+}
+
+impl PacketBase {
+    fn read(r: &mut ByteReader) -> Option<Self> {
+        Some(Self {
+            packet_number: r.u32()?,
+            category_packet_number: r.u32()?,
+            partial_packet_index: r.u8()?,
+            partial_packet_number: r.u8()?,
+            packet_type: r.u8()?,
+            packet_version: r.u8()?,
+        })
+    }
+}
+
+/// Physics of the viewed car. Arrays are front left, front right, rear left, rear right.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsTelemetry {
+    pub viewed_participant_index: i8,   // -1 if no car is viewed
+    pub unfiltered_throttle: u8,        // 0-255
+    pub unfiltered_brake: u8,           // 0-255
+    pub unfiltered_steering: i8,        // -127 (left) to 127 (right)
+    pub unfiltered_clutch: u8,          // 0-255
+    pub car_flags: u8,                  // CAR_*
+    pub oil_temp: i16,                  // Celsius
+    pub oil_pressure: u16,              // KPa
+    pub water_temp: i16,                // Celsius
+    pub water_pressure: u16,            // KPa
+    pub fuel_pressure: u16,             // KPa
+    pub fuel_capacity: u8,              // Liters
+    pub brake: u8,                      // 0-255
+    pub throttle: u8,                   // 0-255
+    pub clutch: u8,                     // 0-255
+    pub fuel_level: f32,                // 0-1
+    pub speed: f32,                     // M/S
+    pub rpm: u16,
+    pub max_rpm: u16,
+    pub steering: i8,                   // -127 (left) to 127 (right)
+    pub gear_num_gears: u8,             // Gear in the low nibble (0 = neutral, 15 = reverse), number of gears in the high nibble
+    pub boost_amount: u8,               // 0-100, push-to-pass/ERS boost
+    pub crash_state: u8,
+    pub odometer: f32,                  // Kilometers
+    pub orientation: [f32; 3],          // Radians, pitch, heading, roll
+    pub local_velocity: [f32; 3],       // M/S in car space
+    pub world_velocity: [f32; 3],       // M/S in world space, x right, y up, z forward
+    pub angular_velocity: [f32; 3],     // Radians per second
+    pub local_acceleration: [f32; 3],   // M/S^2 in car space
+    pub world_acceleration: [f32; 3],   // M/S^2 in world space, x right, y up, z forward
+    pub extents_centre: [f32; 3],
+    pub tyre_flags: [u8; 4],
+    pub terrain: [u8; 4],
+    pub tyre_y: [f32; 4],
+    pub tyre_rps: [f32; 4],             // Revolutions per second
+    pub tyre_temp: [u8; 4],             // Celsius
+    pub tyre_height_above_ground: [f32; 4],
+    pub tyre_wear: [u8; 4],             // 0-255
+    pub brake_damage: [u8; 4],          // 0-255
+    pub suspension_damage: [u8; 4],     // 0-255
+    pub brake_temp: [i16; 4],           // Celsius
+    pub tyre_tread_temp: [u16; 4],      // Kelvin
+    pub tyre_layer_temp: [u16; 4],      // Kelvin
+    pub tyre_carcass_temp: [u16; 4],    // Kelvin
+    pub tyre_rim_temp: [u16; 4],        // Kelvin
+    pub tyre_internal_air_temp: [u16; 4], // Kelvin
+    pub tyre_temp_left: [u16; 4],       // Kelvin
+    pub tyre_temp_center: [u16; 4],     // Kelvin
+    pub tyre_temp_right: [u16; 4],      // Kelvin
+    pub wheel_local_position_y: [f32; 4],
+    pub ride_height: [f32; 4],          // Meters
+    pub suspension_travel: [f32; 4],    // Meters
+    pub suspension_velocity: [f32; 4],  // M/S
+    pub suspension_ride_height: [u16; 4], // Millimeters
+    pub air_pressure: [u16; 4],
+    pub engine_speed: f32,              // Radians per second
+    pub engine_torque: f32,             // Newton meter
+    pub wings: [u8; 2],
+    pub hand_brake: u8,                 // 0-255
+    pub aero_damage: u8,                // 0-255
+    pub engine_damage: u8,              // 0-255
+    pub joy_pad: u32,
+    pub d_pad: u8,
+    pub tyre_compound: [String; 4],
+    pub turbo_boost_pressure: f32,
+    pub full_position: [f32; 3],        // Meters in world space, x right, y up, z forward
+    pub brake_bias: u8,                 // Quantized, 0-255
+    pub tick_count: u32,
+}
+
+impl SmsTelemetry {
+    /// Current gear, -1 for reverse and 0 for neutral
+    pub fn gear(&self) -> isize {
+        match self.gear_num_gears & 0x0f {
+            15 => -1,
+            gear => gear as isize,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsRaceData {
+    pub world_fastest_lap_time: f32,    // Seconds
+    pub personal_fastest_lap_time: f32, // Seconds
+    pub personal_fastest_sector_times: [f32; 3], // Seconds
+    pub world_fastest_sector_times: [f32; 3], // Seconds
+    pub track_length: f32,              // Meters
+    pub track_location: String,
+    pub track_variation: String,
+    pub translated_track_location: String,
+    pub translated_track_variation: String,
+    pub laps_time_in_event: u16,        // Laps, or the duration in 5 minute steps with the top bit set for timed sessions
+    pub enforced_pit_stop_lap: i8,      // -1 if there is none
+}
+
+impl SmsRaceData {
+    /// Number of laps in a lap based session
+    pub fn laps(&self) -> Option<u32> {
+        if self.laps_time_in_event & 0x8000 == 0 && self.laps_time_in_event > 0 { Some(self.laps_time_in_event as u32) } else { None }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsParticipants {
+    pub participants_changed_timestamp: u32,
+    pub names: Vec<String>,             // Always PARTICIPANTS_PER_PACKET, unused slots are empty
+    pub nationalities: Vec<u32>,
+    pub indices: Vec<u16>,              // Participant index of each name
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsParticipantInfo {
+    pub world_position: [i16; 3],       // Meters, extra precision is packed into `sector`
+    pub orientation: [i16; 3],          // Quantized heading, pitch and bank, -PI to PI
+    pub current_lap_distance: u16,      // Meters
+    pub race_position: u8,              // Position in the low 7 bits, top bit set if the participant is active
+    pub sector: u8,                     // Sector in the low 3 bits
+    pub highest_flag: u8,
+    pub pit_mode_schedule: u8,          // Pit mode in the low 3 bits, pit schedule in the high bits
+    pub car_index: u16,                 // Top bit set for human players
+    pub race_state: u8,                 // Race state in the low 3 bits
+    pub current_lap: u8,
+    pub current_time: f32,              // Seconds into the current lap, -1 if invalid
+    pub current_sector_time: f32,       // Seconds
+    pub mp_participant_index: u16,
+}
+
+impl SmsParticipantInfo {
+    pub fn position(&self) -> u8 {
+        self.race_position & 0x7f
+    }
+
+    /// 0 = on track, 1 = driving into the pits, 2 = in the pits, 3 = driving out of the pits, 4 = in the garage, 5 = driving out of the garage
+    pub fn pit_mode(&self) -> u8 {
+        self.pit_mode_schedule & 0x07
+    }
+
+    /// 0 = invalid, 1 = not started, 2 = racing, 3 = finished, 4 = disqualified, 5 = retired, 6 = DNF
+    pub fn race_state(&self) -> u8 {
+        self.race_state & 0x07
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsTimings {
+    pub num_participants: i8,
+    pub participants_changed_timestamp: u32,
+    pub event_time_remaining: f32,      // Seconds, negative for lap based sessions
+    pub split_time_ahead: f32,          // Seconds, to the car ahead of the local player
+    pub split_time_behind: f32,         // Seconds, to the car behind the local player
+    pub split_time: f32,
+    pub participants: Vec<SmsParticipantInfo>, // Always MAX_PARTICIPANTS, only the first `num_participants` are used
+    pub local_participant_index: u16,
+    pub tick_count: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsGameState {
+    pub build_version_number: u16,
+    pub game_state: u8,                 // Low 3 bits game state, next 3 bits session state
+    pub ambient_temperature: i8,        // Celsius
+    pub track_temperature: i8,          // Celsius
+    pub rain_density: u8,               // 0-255
+    pub snow_density: u8,               // 0-255
+    pub wind_speed: i8,
+    pub wind_direction: [i8; 2],
+}
+
+impl SmsGameState {
+    /// 0 = exited, 1 = front end, 2 = playing, 3 = paused, 4 = in menu with time ticking, 5 = restarting, 6 = replay, 7 = front end replay
+    pub fn game(&self) -> u8 {
+        self.game_state & 0x07
+    }
+
+    /// 0 = invalid, 1 = practice, 2 = test, 3 = qualifying, 4 = formation lap, 5 = race, 6 = time attack
+    pub fn session(&self) -> u8 {
+        (self.game_state >> 3) & 0x07
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsParticipantStats {
+    pub fastest_lap_time: f32,          // Seconds
+    pub last_lap_time: f32,             // Seconds
+    pub last_sector_time: f32,          // Seconds
+    pub fastest_sector_times: [f32; 3], // Seconds
+    pub online_rep: u32,
+    pub mp_participant_index: u16,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmsTimeStats {
+    pub participants_changed_timestamp: u32,
+    pub participants: Vec<SmsParticipantStats>, // Always MAX_PARTICIPANTS
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SmsPacket {
+    Telemetry(Box<SmsTelemetry>),
+    RaceData(SmsRaceData),
+    Participants(SmsParticipants),
+    Timings(SmsTimings),
+    GameState(SmsGameState),
+    TimeStats(SmsTimeStats),
+    Other,                              // Packet types we don't decode
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSms {
+    pub base: PacketBase,
+    pub packet: SmsPacket,
+}
+
+impl DataSms {
+    /// Decodes a single packet, the whole datagram must be passed in.
+    pub fn parse(buf: &[u8]) -> Result<Self, SmsError> {
+        let mut r = ByteReader::new(buf);
+        let base = PacketBase::read(&mut r).ok_or(SmsError::TooShort(buf.len()))?;
+        let expected = match Self::packet_size(base.packet_type) {
+            Some(expected) => expected,
+            None => return Ok(Self { base, packet: SmsPacket::Other }),
+        };
+        if buf.len() < expected {
+            return Err(SmsError::TooShort(buf.len()));
+        }
+
+        let packet = match base.packet_type {
+            PACKET_TYPE_TELEMETRY => Self::read_telemetry(&mut r).map(|t| SmsPacket::Telemetry(Box::new(t))),
+            PACKET_TYPE_RACE_DEFINITION => Self::read_race_data(&mut r).map(SmsPacket::RaceData),
+            PACKET_TYPE_PARTICIPANTS => Self::read_participants(&mut r).map(SmsPacket::Participants),
+            PACKET_TYPE_TIMINGS => Self::read_timings(&mut r).map(SmsPacket::Timings),
+            PACKET_TYPE_GAME_STATE => Self::read_game_state(&mut r).map(SmsPacket::GameState),
+            PACKET_TYPE_TIME_STATS => Self::read_time_stats(&mut r).map(SmsPacket::TimeStats),
+            _ => unreachable!(),
+        }.ok_or(SmsError::TooShort(buf.len()))?;
+
+        Ok(Self {
+            base,
+            packet,
+        })
+    }
+
+    /// Minimum size of the packets we decode, None for the ones we don't
+    pub fn packet_size(packet_type: u8) -> Option<usize> {
+        match packet_type {
+            PACKET_TYPE_TELEMETRY => Some(559),
+            PACKET_TYPE_RACE_DEFINITION => Some(307),
+            PACKET_TYPE_PARTICIPANTS => Some(1136),
+            PACKET_TYPE_TIMINGS => Some(1063),
+            PACKET_TYPE_GAME_STATE => Some(22),
+            PACKET_TYPE_TIME_STATS => Some(1040),
+            _ => None,
+        }
+    }
+
+    fn read_telemetry(r: &mut ByteReader) -> Option<SmsTelemetry> {
+        Some(SmsTelemetry {
+            viewed_participant_index: r.i8()?,
+            unfiltered_throttle: r.u8()?,
+            unfiltered_brake: r.u8()?,
+            unfiltered_steering: r.i8()?,
+            unfiltered_clutch: r.u8()?,
+            car_flags: r.u8()?,
+            oil_temp: r.i16()?,
+            oil_pressure: r.u16()?,
+            water_temp: r.i16()?,
+            water_pressure: r.u16()?,
+            fuel_pressure: r.u16()?,
+            fuel_capacity: r.u8()?,
+            brake: r.u8()?,
+            throttle: r.u8()?,
+            clutch: r.u8()?,
+            fuel_level: r.f32()?,
+            speed: r.f32()?,
+            rpm: r.u16()?,
+            max_rpm: r.u16()?,
+            steering: r.i8()?,
+            gear_num_gears: r.u8()?,
+            boost_amount: r.u8()?,
+            crash_state: r.u8()?,
+            odometer: r.f32()?,
+            orientation: r.array(ByteReader::f32)?,
+            local_velocity: r.array(ByteReader::f32)?,
+            world_velocity: r.array(ByteReader::f32)?,
+            angular_velocity: r.array(ByteReader::f32)?,
+            local_acceleration: r.array(ByteReader::f32)?,
+            world_acceleration: r.array(ByteReader::f32)?,
+            extents_centre: r.array(ByteReader::f32)?,
+            tyre_flags: r.bytes()?,
+            terrain: r.bytes()?,
+            tyre_y: r.array(ByteReader::f32)?,
+            tyre_rps: r.array(ByteReader::f32)?,
+            tyre_temp: r.bytes()?,
+            tyre_height_above_ground: r.array(ByteReader::f32)?,
+            tyre_wear: r.bytes()?,
+            brake_damage: r.bytes()?,
+            suspension_damage: r.bytes()?,
+            brake_temp: r.array(ByteReader::i16)?,
+            tyre_tread_temp: r.array(ByteReader::u16)?,
+            tyre_layer_temp: r.array(ByteReader::u16)?,
+            tyre_carcass_temp: r.array(ByteReader::u16)?,
+            tyre_rim_temp: r.array(ByteReader::u16)?,
+            tyre_internal_air_temp: r.array(ByteReader::u16)?,
+            tyre_temp_left: r.array(ByteReader::u16)?,
+            tyre_temp_center: r.array(ByteReader::u16)?,
+            tyre_temp_right: r.array(ByteReader::u16)?,
+            wheel_local_position_y: r.array(ByteReader::f32)?,
+            ride_height: r.array(ByteReader::f32)?,
+            suspension_travel: r.array(ByteReader::f32)?,
+            suspension_velocity: r.array(ByteReader::f32)?,
+            suspension_ride_height: r.array(ByteReader::u16)?,
+            air_pressure: r.array(ByteReader::u16)?,
+            engine_speed: r.f32()?,
+            engine_torque: r.f32()?,
+            wings: r.bytes()?,
+            hand_brake: r.u8()?,
+            aero_damage: r.u8()?,
+            engine_damage: r.u8()?,
+            joy_pad: r.u32()?,
+            d_pad: r.u8()?,
+            tyre_compound: [
+                decode_cstr(r.slice(TYRE_NAME_LENGTH)?),
+                decode_cstr(r.slice(TYRE_NAME_LENGTH)?),
+                decode_cstr(r.slice(TYRE_NAME_LENGTH)?),
+                decode_cstr(r.slice(TYRE_NAME_LENGTH)?),
+            ],
+            turbo_boost_pressure: r.f32()?,
+            full_position: r.array(ByteReader::f32)?,
+            brake_bias: r.u8()?,
+            tick_count: r.u32()?,
+        })
+    }
+
+    fn read_race_data(r: &mut ByteReader) -> Option<SmsRaceData> {
+        Some(SmsRaceData {
+            world_fastest_lap_time: r.f32()?,
+            personal_fastest_lap_time: r.f32()?,
+            personal_fastest_sector_times: r.array(ByteReader::f32)?,
+            world_fastest_sector_times: r.array(ByteReader::f32)?,
+            track_length: r.f32()?,
+            track_location: decode_cstr(r.slice(STRING_LENGTH)?),
+            track_variation: decode_cstr(r.slice(STRING_LENGTH)?),
+            translated_track_location: decode_cstr(r.slice(STRING_LENGTH)?),
+            translated_track_variation: decode_cstr(r.slice(STRING_LENGTH)?),
+            laps_time_in_event: r.u16()?,
+            enforced_pit_stop_lap: r.i8()?,
+        })
+    }
+
+    fn read_participants(r: &mut ByteReader) -> Option<SmsParticipants> {
+        let participants_changed_timestamp = r.u32()?;
+        let names = (0..PARTICIPANTS_PER_PACKET).map(|_| r.slice(STRING_LENGTH).map(decode_cstr)).collect::<Option<_>>()?;
+        let nationalities = (0..PARTICIPANTS_PER_PACKET).map(|_| r.u32()).collect::<Option<_>>()?;
+        let indices = (0..PARTICIPANTS_PER_PACKET).map(|_| r.u16()).collect::<Option<_>>()?;
+        Some(SmsParticipants {
+            participants_changed_timestamp,
+            names,
+            nationalities,
+            indices,
+        })
+    }
+
+    fn read_participant_info(r: &mut ByteReader) -> Option<SmsParticipantInfo> {
+        Some(SmsParticipantInfo {
+            world_position: r.array(ByteReader::i16)?,
+            orientation: r.array(ByteReader::i16)?,
+            current_lap_distance: r.u16()?,
+            race_position: r.u8()?,
+            sector: r.u8()?,
+            highest_flag: r.u8()?,
+            pit_mode_schedule: r.u8()?,
+            car_index: r.u16()?,
+            race_state: r.u8()?,
+            current_lap: r.u8()?,
+            current_time: r.f32()?,
+            current_sector_time: r.f32()?,
+            mp_participant_index: r.u16()?,
+        })
+    }
+
+    fn read_timings(r: &mut ByteReader) -> Option<SmsTimings> {
+        Some(SmsTimings {
+            num_participants: r.i8()?,
+            participants_changed_timestamp: r.u32()?,
+            event_time_remaining: r.f32()?,
+            split_time_ahead: r.f32()?,
+            split_time_behind: r.f32()?,
+            split_time: r.f32()?,
+            participants: (0..MAX_PARTICIPANTS).map(|_| Self::read_participant_info(r)).collect::<Option<_>>()?,
+            local_participant_index: r.u16()?,
+            tick_count: r.u32()?,
+        })
+    }
+
+    fn read_game_state(r: &mut ByteReader) -> Option<SmsGameState> {
+        Some(SmsGameState {
+            build_version_number: r.u16()?,
+            game_state: r.u8()?,
+            ambient_temperature: r.i8()?,
+            track_temperature: r.i8()?,
+            rain_density: r.u8()?,
+            snow_density: r.u8()?,
+            wind_speed: r.i8()?,
+            wind_direction: [r.i8()?, r.i8()?],
+        })
+    }
+
+    fn read_participant_stats(r: &mut ByteReader) -> Option<SmsParticipantStats> {
+        Some(SmsParticipantStats {
+            fastest_lap_time: r.f32()?,
+            last_lap_time: r.f32()?,
+            last_sector_time: r.f32()?,
+            fastest_sector_times: r.array(ByteReader::f32)?,
+            online_rep: r.u32()?,
+            mp_participant_index: r.u16()?,
+        })
+    }
+
+    fn read_time_stats(r: &mut ByteReader) -> Option<SmsTimeStats> {
+        let participants_changed_timestamp = r.u32()?;
+        let participants = (0..MAX_PARTICIPANTS).map(|_| {
+            let stats = Self::read_participant_stats(r)?;
+            r.skip(2)?; // Padding
+            Some(stats)
+        }).collect::<Option<_>>()?;
+        Some(SmsTimeStats {
+            participants_changed_timestamp,
+            participants,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error decoding an SMS UDP packet
+pub enum SmsError {
+    /// the packet is shorter than its type requires
+    TooShort(usize),
+}
+
+impl std::fmt::Display for SmsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base(packet_type: u8, partial_packet_index: u8, partial_packet_number: u8) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&1234u32.to_le_bytes());
+        buf.extend_from_slice(&56u32.to_le_bytes());
+        buf.extend_from_slice(&[partial_packet_index, partial_packet_number, packet_type, 2]);
+        buf
+    }
+
+    fn telemetry() -> Vec<u8> {
+        let mut buf = base(PACKET_TYPE_TELEMETRY, 1, 1);
+        buf.extend_from_slice(&[3, 255, 0, 0, 0, CAR_HEADLIGHT | CAR_SPEED_LIMITER]);
+        for v in [112i16 as u16, 450, 89, 120, 300] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&[100, 0, 255, 0]);
+        buf.extend_from_slice(&0.4f32.to_le_bytes());
+        buf.extend_from_slice(&41.5f32.to_le_bytes());
+        buf.extend_from_slice(&7200u16.to_le_bytes());
+        buf.extend_from_slice(&8500u16.to_le_bytes());
+        buf.extend_from_slice(&[0, 0x64, 0, 0]);
+        buf.extend_from_slice(&1520.0f32.to_le_bytes());
+        // Orientation, velocities and accelerations, then the extents
+        for v in [0.01f32, 1.5, -0.02] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.resize(136, 0);
+        buf.extend_from_slice(&[0; 8]);
+        buf.resize(176, 0);
+        buf.extend_from_slice(&[80, 81, 90, 91]);
+        buf.resize(208, 0);
+        for t in [400i16, 410, 300, 310] {
+            buf.extend_from_slice(&t.to_le_bytes());
+        }
+        buf.resize(312, 0);
+        for v in [0.05f32, 0.05, 0.04, 0.04] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.resize(378, 0);
+        let mut compound = b"Soft".to_vec();
+        compound.resize(TYRE_NAME_LENGTH, 0);
+        for _ in 0..4 {
+            buf.extend_from_slice(&compound);
+        }
+        buf.extend_from_slice(&0.0f32.to_le_bytes());
+        for v in [-120.5f32, 12.0, 800.25] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.push(140);
+        buf.extend_from_slice(&99u32.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn parse_telemetry() {
+        let buf = telemetry();
+        assert_eq!(buf.len(), 559);
+        let raw = DataSms::parse(&buf).unwrap();
+        assert_eq!(raw.base.packet_version, 2);
+        let SmsPacket::Telemetry(car) = raw.packet else { panic!("expected telemetry") };
+        assert_eq!(car.viewed_participant_index, 3);
+        assert_eq!(car.car_flags, CAR_HEADLIGHT | CAR_SPEED_LIMITER);
+        assert_eq!(car.oil_temp, 112);
+        assert_eq!(car.fuel_capacity, 100);
+        assert_eq!(car.throttle, 255);
+        assert_eq!(car.fuel_level, 0.4);
+        assert_eq!(car.rpm, 7200);
+        assert_eq!(car.max_rpm, 8500);
+        assert_eq!(car.gear(), 4);
+        assert_eq!(car.gear_num_gears >> 4, 6);
+        assert_eq!(car.orientation, [0.01, 1.5, -0.02]);
+        assert_eq!(car.tyre_temp, [80, 81, 90, 91]);
+        assert_eq!(car.brake_temp, [400, 410, 300, 310]);
+        assert_eq!(car.suspension_travel, [0.05, 0.05, 0.04, 0.04]);
+        assert_eq!(car.tyre_compound[3], "Soft");
+        assert_eq!(car.full_position, [-120.5, 12.0, 800.25]);
+        assert_eq!(car.tick_count, 99);
+    }
+
+    #[test]
+    fn parse_timings() {
+        let mut buf = base(PACKET_TYPE_TIMINGS, 1, 1);
+        buf.push(2);
+        buf.extend_from_slice(&7u32.to_le_bytes());
+        for v in [-1.0f32, 0.8, 1.2, 0.0] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in 0..MAX_PARTICIPANTS {
+            let mut info = vec![0u8; 12];
+            info.extend_from_slice(&(1000 + i as u16).to_le_bytes());
+            info.extend_from_slice(&[0x80 | (i as u8 + 1), 1, 0, 2]);
+            info.extend_from_slice(&0u16.to_le_bytes());
+            info.extend_from_slice(&[2, 4]);
+            info.extend_from_slice(&61.25f32.to_le_bytes());
+            info.extend_from_slice(&[0; 6]);
+            buf.extend(info);
+        }
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&99u32.to_le_bytes());
+
+        let SmsPacket::Timings(timings) = DataSms::parse(&buf).unwrap().packet else { panic!("expected timings") };
+        assert_eq!(timings.participants.len(), MAX_PARTICIPANTS);
+        assert_eq!(timings.split_time_ahead, 0.8);
+        assert_eq!(timings.local_participant_index, 1);
+        let info = &timings.participants[1];
+        assert_eq!(info.current_lap_distance, 1001);
+        assert_eq!(info.position(), 2);
+        assert_eq!(info.pit_mode(), 2);
+        assert_eq!(info.race_state(), 2);
+        assert_eq!(info.current_lap, 4);
+        assert_eq!(info.current_time, 61.25);
+    }
+
+    #[test]
+    fn parse_race_data() {
+        let mut buf = base(PACKET_TYPE_RACE_DEFINITION, 1, 1);
+        for _ in 0..8 {
+            buf.extend_from_slice(&(-1.0f32).to_le_bytes());
+        }
+        buf.extend_from_slice(&5793.0f32.to_le_bytes());
+        for name in ["Monza", "GP", "Monza", "Grand Prix"] {
+            let mut field = name.as_bytes().to_vec();
+            field.resize(STRING_LENGTH, 0);
+            buf.extend(field);
+        }
+        buf.extend_from_slice(&12u16.to_le_bytes());
+        buf.extend_from_slice(&[255, 0]);
+
+        let SmsPacket::RaceData(race) = DataSms::parse(&buf).unwrap().packet else { panic!("expected race data") };
+        assert_eq!(race.track_length, 5793.0);
+        assert_eq!(race.translated_track_variation, "Grand Prix");
+        assert_eq!(race.laps(), Some(12));
+        assert_eq!(race.enforced_pit_stop_lap, -1);
+
+        // Timed sessions set the top bit
+        let n = buf.len();
+        buf[n - 4..n - 2].copy_from_slice(&0x8006u16.to_le_bytes());
+        let SmsPacket::RaceData(race) = DataSms::parse(&buf).unwrap().packet else { panic!("expected race data") };
+        assert_eq!(race.laps(), None);
+    }
+
+    #[test]
+    fn reject_short_packets() {
+        assert_eq!(DataSms::parse(&[0; 8]), Err(SmsError::TooShort(8)));
+        let buf = telemetry();
+        assert_eq!(DataSms::parse(&buf[..400]), Err(SmsError::TooShort(400)));
+        // Packet types we don't decode are passed through
+        let raw = DataSms::parse(&base(8, 1, 2)).unwrap();
+        assert_eq!(raw.packet, SmsPacket::Other);
+    }
+}
@@ -0,0 +1,137 @@
+// Settings that outlive the app, kept in a TOML file next to where the app is started.
+// Missing fields fall back to their defaults, so an empty or outdated file is always valid.
+// The file is laid over the whole default config rather than deserialized on its own: each game's settings have
+// their own defaults (its port, its packet layout), which a field missing from a game's section has to keep.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::GamesConfig;
+use crate::hardware::HardwareConfig;
+
+pub const DEFAULT_PATH: &str = "dysoon_simhub.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub games: GamesConfig,
+    pub hardware: HardwareConfig,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let file: toml::Value = toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        let mut config = toml::Value::try_from(Self::default()).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+        merge(&mut config, file);
+        config.try_into().map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Falls back to the defaults if the file is missing or broken, a broken file is left alone so it can be fixed
+    pub fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            info!("No config file at {}, using the defaults", path.display());
+            return Self::default();
+        }
+        match Self::load(path) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Could not load config file {}, using the defaults: {e}", path.display());
+                Self::default()
+            },
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let text = toml::to_string_pretty(self).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+        std::fs::write(path, text).map_err(|e| ConfigError::Io(e.to_string()))
+    }
+}
+
+/// Replaces what `file` sets in `config`, tables are merged key by key and everything else replaced as a whole
+fn merge(config: &mut toml::Value, file: toml::Value) {
+    match (config, file) {
+        (toml::Value::Table(config), toml::Value::Table(file)) => {
+            for (key, value) in file {
+                match config.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        config.insert(key, value);
+                    },
+                }
+            }
+        },
+        (config, file) => *config = file,
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error loading or saving the config file
+pub enum ConfigError {
+    /// file could not be read or written
+    Io(String),
+    /// file isn't valid TOML, or has a setting of the wrong type
+    Parse(String),
+    /// settings could not be written as TOML
+    Serialize(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{Calibration, CalibrationPoint, DeviceConfig, DeviceId, Interpolation};
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("dysoon_simhub_config_{}.toml", std::process::id()));
+        let mut config = Config::default();
+        config.games.beamng.outgauge.bind_address = "0.0.0.0".to_string();
+        config.games.beamng.outgauge.source_filter = "192.168.1.20".to_string();
+        config.games.forza.listen.port = 5301;
+        config.hardware.update_rate_hz = 120.0;
+        config.hardware.devices.push(DeviceConfig {
+            id: DeviceId("DG-0042".to_string()),
+            name: "Left gauge".to_string(),
+            enabled: false,
+            calibration: Calibration {
+                interpolation: Interpolation::Spline,
+                points: vec![CalibrationPoint { value: 0.0, raw: 12.0 }, CalibrationPoint { value: 8000.0, raw: 7950.5 }],
+            },
+        });
+        config.save(&path).unwrap();
+        let loaded = Config::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Ok(config));
+    }
+
+    #[test]
+    fn partial_file() {
+        let defaults = Config::default();
+        let config = Config::parse("[games.beamng.outgauge]\nport = 4445\n").unwrap();
+        assert_eq!(config.games.beamng.outgauge.port, 4445);
+        assert_eq!(config.games.beamng.outgauge.bind_address, "127.0.0.1");
+        assert_eq!(config.games.beamng.outsim, defaults.games.beamng.outsim);
+        assert_eq!(config.games.ea_wrc, defaults.games.ea_wrc);
+        assert!(matches!(Config::parse("[games.forza.listen]\nport = \"x\"\n"), Err(ConfigError::Parse(_))));
+
+        // A game's section only changes what it sets, the rest keeps that game's defaults rather than the type's
+        let config = Config::parse("[games.forza.listen]\nbind_address = \"0.0.0.0\"\n[games.ea_wrc.listen]\nport = 20778\n").unwrap();
+        assert_eq!(config.games.forza.listen.bind_address, "0.0.0.0");
+        assert_eq!(config.games.forza.listen.port, defaults.games.forza.listen.port);
+        assert_ne!(config.games.forza.listen.port, 0);
+        assert_eq!(config.games.ea_wrc.listen.port, 20778);
+        assert_eq!(config.games.ea_wrc.layout, defaults.games.ea_wrc.layout);
+        assert_eq!(Config::parse(""), Ok(defaults));
+    }
+}
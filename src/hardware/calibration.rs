@@ -0,0 +1,143 @@
+// Gauge needles don't move linearly with the value they're sent: the motor, the dial's print and the zero offset
+// all add their own error. A calibration maps what the dial should show to what has to be sent for the needle to
+// point there, measured at a few reference points and interpolated in between.
+// The spline is a monotone cubic, so a curve through rising points never overshoots or runs backwards.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    pub value: f32,                 // What the dial should show
+    pub raw: f32,                   // What has to be sent to the device for the needle to show it
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Spline,
+}
+
+/// A device's calibration curve, without points it passes values through unchanged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    pub interpolation: Interpolation,
+    pub points: Vec<CalibrationPoint>,
+}
+
+impl Calibration {
+    pub fn is_calibrated(&self) -> bool {
+        self.points.len() >= 2
+    }
+
+    /// The raw value to send for the dial to show `value`. Values past the first or last point get that point's raw value.
+    pub fn apply(&self, value: f32) -> f32 {
+        if !self.is_calibrated() {
+            return value;
+        }
+        // Hand edited settings files may have them in any order
+        let mut points = self.points.clone();
+        points.sort_by(|a, b| a.value.total_cmp(&b.value));
+        points.dedup_by(|a, b| a.value == b.value);
+
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if points.len() < 2 || value <= first.value {
+            return first.raw;
+        }
+        if value >= last.value {
+            return last.raw;
+        }
+        let k = points.windows(2).position(|pair| value < pair[1].value).unwrap_or(points.len() - 2);
+        let (a, b) = (points[k], points[k + 1]);
+        let h = b.value - a.value;
+        let t = (value - a.value) / h;
+        match self.interpolation {
+            Interpolation::Linear => a.raw + (b.raw - a.raw) * t,
+            Interpolation::Spline => {
+                let tangents = monotone_tangents(&points);
+                let (t2, t3) = (t * t, t * t * t);
+                (2.0 * t3 - 3.0 * t2 + 1.0) * a.raw
+                    + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                    + (-2.0 * t3 + 3.0 * t2) * b.raw
+                    + (t3 - t2) * h * tangents[k + 1]
+            },
+        }
+    }
+}
+
+/// Fritsch-Carlson tangents for a cubic Hermite spline through sorted points
+fn monotone_tangents(points: &[CalibrationPoint]) -> Vec<f32> {
+    let slopes: Vec<f32> = points.windows(2).map(|pair| (pair[1].raw - pair[0].raw) / (pair[1].value - pair[0].value)).collect();
+    let mut tangents = Vec::with_capacity(points.len());
+    tangents.push(slopes[0]);
+    for pair in slopes.windows(2) {
+        // A local extreme stays flat, otherwise the curve would overshoot it
+        tangents.push(if pair[0] * pair[1] <= 0.0 { 0.0 } else { (pair[0] + pair[1]) / 2.0 });
+    }
+    tangents.push(slopes[slopes.len() - 1]);
+
+    for (k, &slope) in slopes.iter().enumerate() {
+        if slope == 0.0 {
+            tangents[k] = 0.0;
+            tangents[k + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (tangents[k] / slope, tangents[k + 1] / slope);
+        let s = a * a + b * b;
+        if s > 9.0 {
+            let t = 3.0 / s.sqrt();
+            tangents[k] = t * a * slope;
+            tangents[k + 1] = t * b * slope;
+        }
+    }
+    tangents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(interpolation: Interpolation) -> Calibration {
+        // A needle that lags in the middle of the dial
+        let points = [(0.0, 0.0), (2000.0, 2300.0), (4000.0, 4600.0), (6000.0, 6300.0), (8000.0, 8000.0)];
+        Calibration {
+            interpolation,
+            points: points.iter().rev().map(|&(value, raw)| CalibrationPoint { value, raw }).collect(),
+        }
+    }
+
+    #[test]
+    fn uncalibrated() {
+        assert_eq!(Calibration::default().apply(1234.0), 1234.0);
+        let one_point = Calibration { points: vec![CalibrationPoint { value: 0.0, raw: 50.0 }], ..Default::default() };
+        assert_eq!(one_point.apply(1234.0), 1234.0);
+    }
+
+    #[test]
+    fn linear() {
+        let calibration = curve(Interpolation::Linear);
+        assert_eq!(calibration.apply(2000.0), 2300.0);
+        assert_eq!(calibration.apply(3000.0), 3450.0);
+        assert_eq!(calibration.apply(7000.0), 7150.0);
+        assert_eq!(calibration.apply(-100.0), 0.0);
+        assert_eq!(calibration.apply(9000.0), 8000.0);
+    }
+
+    #[test]
+    fn spline_passes_through_points_without_overshooting() {
+        let calibration = curve(Interpolation::Spline);
+        for point in &calibration.points {
+            assert!((calibration.apply(point.value) - point.raw).abs() < 1e-2);
+        }
+        let mut last = calibration.apply(0.0);
+        for value in (1..=800).map(|v| v as f32 * 10.0) {
+            let raw = calibration.apply(value);
+            assert!(raw >= last, "{value} went backwards");
+            assert!((0.0..=8000.0).contains(&raw));
+            last = raw;
+        }
+        // Smoother than linear in between, but close to it
+        assert!((calibration.apply(3000.0) - 3450.0).abs() < 100.0);
+    }
+}
@@ -1,16 +1,93 @@
+use serde::{Deserialize, Serialize};
+
 pub mod rpm_gauge;
+pub mod settings;
+
+/// Names one physical device across reconnects and restarts: its serial number,
+/// or the port it's plugged into if it doesn't have one
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DeviceId(pub String);
+
+impl DeviceId {
+    pub fn of(info: &hidapi::DeviceInfo) -> Self {
+        match info.serial_number().map(str::trim) {
+            Some(serial) if !serial.is_empty() => Self(serial.to_string()),
+            _ => Self(Self::port(info)),
+        }
+    }
+
+    /// Two devices with the same serial number (cloned firmware) still need their own ID
+    pub fn with_port(&self, info: &hidapi::DeviceInfo) -> Self {
+        Self(format!("{}@{}", self.0, Self::port(info)))
+    }
+
+    fn port(info: &hidapi::DeviceInfo) -> String {
+        format!("{:04x}:{:04x}@{}", info.vendor_id(), info.product_id(), info.path().to_string_lossy())
+    }
+}
+
+impl std::fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// What the app gets to know about a device, connected or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceDescriptor {
+    pub id: DeviceId,
+    pub kind: DeviceKind,
+    pub unit_type: u8,                  // As reported by the handshake
+    pub max_value: u16,                 // Highest value the dial shows, in its unit
+    pub serial: String,                 // Empty if the device has none
+    pub firmware_version: String,       // From the USB release number
+    pub state: ConnectionState,
+    pub last_error: Option<String>,
+}
+
+impl DeviceDescriptor {
+    pub fn new(id: DeviceId, info: &hidapi::DeviceInfo) -> Self {
+        let release = info.release_number();
+        Self {
+            id,
+            kind: DeviceKind::Unknown,
+            unit_type: 0,
+            max_value: 0,
+            serial: info.serial_number().unwrap_or_default().to_string(),
+            // Binary coded decimal, 0x0102 is version 1.02
+            firmware_version: format!("{:x}.{:02x}", release >> 8, release & 0xff),
+            state: ConnectionState::Disconnected,
+            last_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DeviceKind {
+    RpmGauge,
+    Unknown,                            // The handshake never succeeded
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    HandshakeFailed,
+}
 
 pub enum Device {
     RpmGauge(rpm_gauge::RpmGauge),
 }
 
 impl Device {
-    pub fn from_hid_device(api: &hidapi::HidApi, vid: u16, pid: u16) -> anyhow::Result<Self> {
+    /// Opens the device at `info`'s path, so identical devices each get their own handle
+    pub fn from_hid_device(api: &hidapi::HidApi, info: &hidapi::DeviceInfo, id: DeviceId) -> anyhow::Result<Self> {
         const MAGIC: [u8; 5] = [0, 123, 38, 83, 231]; // First zero is the report ID (just 0)
 
-        trace!("Connecting to device {vid}:{pid}");
+        trace!("Connecting to device {id}");
 
-        let device = api.open(vid, pid)?;
+        let device = api.open_path(info.path())?;
         device.set_blocking_mode(true)?;
         device.write(&MAGIC)?;
 
@@ -22,7 +99,8 @@ impl Device {
         // }
 
         let mut read_buf = [0u8; 64];
-        let bytes_read = device.read(&mut read_buf)?;
+        // Something that isn't a gauge may never answer, which must not hang the hardware task. Nothing read is too little.
+        let bytes_read = device.read_timeout(&mut read_buf, settings::REPLY_TIMEOUT_MS)?;
         if bytes_read >= 4 {
             let device_type = read_buf[0];
             let unit_type = read_buf[1];
@@ -31,13 +109,43 @@ impl Device {
             debug!("unit_type: {unit_type}");
             debug!("max_value: {max_value}");
             match device_type {
-                0 => Ok(Device::RpmGauge(rpm_gauge::RpmGauge::new(device, max_value))),
+                0 => Ok(Device::RpmGauge(rpm_gauge::RpmGauge::new(id, device, unit_type, max_value))),
                 _ => Err(InitDeviceError::UnknownDevice.into()),
             }
         } else {
             Err(InitDeviceError::NotEnoughDataRead.into())
         }
     }
+
+    pub fn id(&self) -> &DeviceId {
+        match self {
+            Device::RpmGauge(rpm_gauge) => rpm_gauge.id(),
+        }
+    }
+
+    /// Fills in what the handshake told us
+    pub fn describe(&self, descriptor: &mut DeviceDescriptor) {
+        match self {
+            Device::RpmGauge(rpm_gauge) => {
+                descriptor.kind = DeviceKind::RpmGauge;
+                descriptor.unit_type = rpm_gauge.unit_type();
+                descriptor.max_value = rpm_gauge.max_rpm();
+            },
+        }
+    }
+
+    pub fn configurable(&mut self) -> &mut dyn settings::ConfigurableDevice {
+        match self {
+            Device::RpmGauge(rpm_gauge) => rpm_gauge,
+        }
+    }
+
+    /// Shows a value between 0 and 1 of the dial, for finding a device among identical ones
+    pub fn show_fraction(&self, fraction: f32) -> anyhow::Result<()> {
+        match self {
+            Device::RpmGauge(rpm_gauge) => rpm_gauge.update_rpm((rpm_gauge.max_rpm() as f32 * fraction.clamp(0.0, 1.0)) as u16),
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
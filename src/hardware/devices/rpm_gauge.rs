@@ -1,18 +1,38 @@
 use hidapi::HidDevice;
+use std::time::{Duration, Instant};
+
+use super::DeviceId;
+use super::settings::*;
 
 pub struct RpmGauge {
+    id: DeviceId,
     device: HidDevice,
+    unit_type: u8,
     max_rpm: u16,
 }
 
 impl RpmGauge {
-    pub fn new(device: HidDevice, max_rpm: u16) -> Self {
+    pub fn new(id: DeviceId, device: HidDevice, unit_type: u8, max_rpm: u16) -> Self {
         Self {
+            id,
             device,
+            unit_type,
             max_rpm,
         }
     }
 
+    pub fn id(&self) -> &DeviceId {
+        &self.id
+    }
+
+    pub fn unit_type(&self) -> u8 {
+        self.unit_type
+    }
+
+    pub fn max_rpm(&self) -> u16 {
+        self.max_rpm
+    }
+
     pub fn heartbeat(&self) -> anyhow::Result<()> {
         let mut data: [u8; 2] = [0; 2];
         data[1] = 1;
@@ -29,4 +49,46 @@ impl RpmGauge {
         self.device.write(&data)?;
         Ok(())
     }
+
+    /// Sends a report and waits for the device to acknowledge it, skipping stale replies to earlier requests
+    fn request(&self, report: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let report_id = report[1];
+        self.device.write(report)?;
+
+        let deadline = Instant::now() + Duration::from_millis(REPLY_TIMEOUT_MS as u64);
+        let mut buf = [0u8; 64];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let bytes_read = match remaining.is_zero() {
+                true => 0,
+                false => self.device.read_timeout(&mut buf, remaining.as_millis() as i32)?,
+            };
+            if bytes_read == 0 {
+                return Err(SettingsError::Timeout.into());
+            }
+            match parse_reply(report_id, &buf[..bytes_read]) {
+                Err(SettingsError::UnexpectedReply(id)) => trace!("Ignoring reply to report {id} from {}", self.id),
+                result => return Ok(result?.to_vec()),
+            }
+        }
+    }
+}
+
+impl ConfigurableDevice for RpmGauge {
+    fn read_settings(&self) -> anyhow::Result<DeviceSettings> {
+        let payload = self.request(&[0, READ_SETTINGS])?;
+        Ok(DeviceSettings::parse(&payload)?)
+    }
+
+    fn write_settings(&mut self, settings: &DeviceSettings) -> anyhow::Result<()> {
+        settings.validate()?;
+        let mut data = [0u8; 2 + SETTINGS_SIZE];
+        data[1] = WRITE_SETTINGS;
+        data[2..].copy_from_slice(&settings.encode());
+        self.request(&data)?;
+
+        self.unit_type = settings.unit_type;
+        self.max_rpm = settings.max_value;
+        Ok(())
+    }
 }
@@ -0,0 +1,165 @@
+//! Settings a device keeps in its own flash, so it shows the right dial even before the host talks to it.
+//! The host reads them with a `READ_SETTINGS` report and replaces all of them at once with a `WRITE_SETTINGS` report.
+//! The device answers both with the same report ID and a status byte, followed by the settings for a read.
+//! Multi-byte values are little-endian, like the RPM report.
+
+use serde::{Deserialize, Serialize};
+
+pub const READ_SETTINGS: u8 = 3;
+pub const WRITE_SETTINGS: u8 = 4;
+
+/// Size of the encoded settings
+pub const SETTINGS_SIZE: usize = 10;
+
+/// How long to wait for the device to answer, writing to flash can take a while
+pub const REPLY_TIMEOUT_MS: i32 = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSettings {
+    pub max_value: u16,                 // The value at the end of the dial, in its unit
+    pub unit_type: u8,                  // What the dial shows, as reported by the handshake
+    pub zero_offset: i16,               // Motor steps between the end stop and the dial's zero
+    pub sweep_min: u16,                 // Degrees, where the needle rests at zero
+    pub sweep_max: u16,                 // Degrees, where the needle points at the max value
+    pub brightness: u8,                 // Backlight, 0-255
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        Self {
+            max_value: 8000,
+            unit_type: 0,
+            zero_offset: 0,
+            sweep_min: 0,
+            sweep_max: 270,
+            brightness: 255,
+        }
+    }
+}
+
+impl DeviceSettings {
+    pub fn parse(buf: &[u8]) -> Result<Self, SettingsError> {
+        if buf.len() < SETTINGS_SIZE {
+            return Err(SettingsError::TooShort(buf.len()));
+        }
+        let settings = Self {
+            max_value: u16::from_le_bytes([buf[0], buf[1]]),
+            unit_type: buf[2],
+            zero_offset: i16::from_le_bytes([buf[3], buf[4]]),
+            sweep_min: u16::from_le_bytes([buf[5], buf[6]]),
+            sweep_max: u16::from_le_bytes([buf[7], buf[8]]),
+            brightness: buf[9],
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn encode(&self) -> [u8; SETTINGS_SIZE] {
+        let mut buf = [0u8; SETTINGS_SIZE];
+        buf[0..2].copy_from_slice(&self.max_value.to_le_bytes());
+        buf[2] = self.unit_type;
+        buf[3..5].copy_from_slice(&self.zero_offset.to_le_bytes());
+        buf[5..7].copy_from_slice(&self.sweep_min.to_le_bytes());
+        buf[7..9].copy_from_slice(&self.sweep_max.to_le_bytes());
+        buf[9] = self.brightness;
+        buf
+    }
+
+    /// Catches settings the device would refuse before sending them
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.max_value == 0 {
+            return Err(SettingsError::InvalidValue("max value"));
+        }
+        if self.sweep_min >= self.sweep_max || self.sweep_max > 360 {
+            return Err(SettingsError::InvalidValue("needle sweep range"));
+        }
+        Ok(())
+    }
+}
+
+/// Checks the status of a reply to `report_id` and returns what follows it
+pub fn parse_reply(report_id: u8, buf: &[u8]) -> Result<&[u8], SettingsError> {
+    match buf {
+        [id, ..] if *id != report_id => Err(SettingsError::UnexpectedReply(*id)),
+        [_, 0, payload @ ..] => Ok(payload),
+        [_, 1, ..] => Err(SettingsError::UnknownReport),
+        [_, 2, ..] => Err(SettingsError::Rejected),
+        [_, 3, ..] => Err(SettingsError::StorageFailed),
+        [_, status, ..] => Err(SettingsError::UnknownStatus(*status)),
+        _ => Err(SettingsError::TooShort(buf.len())),
+    }
+}
+
+/// A device whose settings can be read and changed from the host
+pub trait ConfigurableDevice {
+    fn read_settings(&self) -> anyhow::Result<DeviceSettings>;
+
+    /// Stores the settings on the device, which acknowledges once they're in flash
+    fn write_settings(&mut self, settings: &DeviceSettings) -> anyhow::Result<()>;
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+/// error reading or writing device settings
+pub enum SettingsError {
+    /// reply is shorter than expected
+    TooShort(usize),
+    /// device answered with a different report ID
+    UnexpectedReply(u8),
+    /// device didn't answer in time
+    Timeout,
+    /// firmware doesn't know the settings reports
+    UnknownReport,
+    /// device refused a value
+    Rejected,
+    /// device could not write its flash
+    StorageFailed,
+    /// device answered with a status this host doesn't know
+    UnknownStatus(u8),
+    /// setting is out of range
+    InvalidValue(&'static str),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let settings = DeviceSettings {
+            max_value: 9000,
+            unit_type: 1,
+            zero_offset: -12,
+            sweep_min: 10,
+            sweep_max: 250,
+            brightness: 128,
+        };
+        let encoded = settings.encode();
+        assert_eq!(encoded, [0x28, 0x23, 1, 0xf4, 0xff, 10, 0, 250, 0, 128]);
+        assert_eq!(DeviceSettings::parse(&encoded), Ok(settings));
+        assert_eq!(DeviceSettings::parse(&encoded[..9]), Err(SettingsError::TooShort(9)));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let settings = DeviceSettings { sweep_min: 270, ..Default::default() };
+        assert_eq!(settings.validate(), Err(SettingsError::InvalidValue("needle sweep range")));
+        assert_eq!(DeviceSettings { max_value: 0, ..Default::default() }.validate(), Err(SettingsError::InvalidValue("max value")));
+        assert_eq!(DeviceSettings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn replies() {
+        assert_eq!(parse_reply(READ_SETTINGS, &[READ_SETTINGS, 0, 1, 2]), Ok(&[1u8, 2][..]));
+        assert_eq!(parse_reply(WRITE_SETTINGS, &[WRITE_SETTINGS, 0]), Ok(&[][..]));
+        assert_eq!(parse_reply(WRITE_SETTINGS, &[WRITE_SETTINGS, 2]), Err(SettingsError::Rejected));
+        assert_eq!(parse_reply(WRITE_SETTINGS, &[WRITE_SETTINGS, 9]), Err(SettingsError::UnknownStatus(9)));
+        assert_eq!(parse_reply(WRITE_SETTINGS, &[READ_SETTINGS, 0]), Err(SettingsError::UnexpectedReply(READ_SETTINGS)));
+        assert_eq!(parse_reply(READ_SETTINGS, &[READ_SETTINGS]), Err(SettingsError::TooShort(1)));
+    }
+}
@@ -1,88 +1,415 @@
-use tokio::sync::mpsc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Duration, Instant, MissedTickBehavior};
 
 mod devices;
+mod calibration;
 
-use crate::telemetry::Telemetry;
+pub use devices::{ConnectionState, DeviceDescriptor, DeviceId, DeviceKind};
+pub use devices::settings::DeviceSettings;
+pub use calibration::{Calibration, CalibrationPoint, Interpolation};
+
+use crate::telemetry::TelemetryReceiver;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HardwareConfig {
+    pub update_rate_hz: f32,        // How often devices get the newest telemetry, at most
+    pub scan_interval_ms: u64,      // How often to look for devices that were plugged in or came back
+    pub devices: Vec<DeviceConfig>,
+}
+
+impl Default for HardwareConfig {
+    fn default() -> Self {
+        Self {
+            update_rate_hz: 60.0,
+            scan_interval_ms: 1000,
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// Settings for one physical device, devices without any use the defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceConfig {
+    pub id: DeviceId,
+    pub name: String,               // Shown instead of the ID, empty to show the ID
+    pub enabled: bool,              // Disabled devices stay connected but don't show telemetry
+    pub calibration: Calibration,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            id: DeviceId::default(),
+            name: String::new(),
+            enabled: true,
+            calibration: Calibration::default(),
+        }
+    }
+}
+
+impl HardwareConfig {
+    pub fn device(&self, id: &DeviceId) -> Option<&DeviceConfig> {
+        self.devices.iter().find(|device| &device.id == id)
+    }
+
+    /// The device's settings, added with the defaults if it has none yet
+    pub fn device_mut(&mut self, id: &DeviceId) -> &mut DeviceConfig {
+        match self.devices.iter().position(|device| &device.id == id) {
+            Some(index) => &mut self.devices[index],
+            None => {
+                self.devices.push(DeviceConfig { id: id.clone(), ..Default::default() });
+                self.devices.last_mut().unwrap()
+            },
+        }
+    }
+
+    fn is_enabled(&self, id: &DeviceId) -> bool {
+        self.device(id).is_none_or(|device| device.enabled)
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.update_rate_hz.clamp(1.0, 1000.0))
+    }
+
+    fn scan_interval(&self) -> Duration {
+        Duration::from_millis(self.scan_interval_ms.max(100))
+    }
+}
+
+/// Keeps a steady pace without catching up on ticks missed while a device was slow to answer
+fn interval(period: Duration) -> tokio::time::Interval {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
 
 pub enum HwBoundEvent {
-    UpdateTelemetry(Telemetry),
     RequestDeviceList,
+    Identify(DeviceId),
+    ReadSettings(DeviceId),
+    WriteSettings(DeviceId, DeviceSettings),
+    Hold(DeviceId, Option<u16>), // Shows a raw value instead of telemetry until released with None, for calibrating
 }
 
 pub enum AppBoundEvent {
-    UpdateDeviceList(Vec<devices::Device>),
+    UpdateDeviceList(Vec<DeviceDescriptor>),
+    DeviceConnected(DeviceId),
+    DeviceDisconnected(DeviceId, String), // With the error that gave it away
+    Settings(DeviceId, Result<DeviceSettings, String>),
+    SettingsWritten(DeviceId, Result<(), String>), // Acknowledged by the device, or why not
 }
 
-pub async fn main(mut rx: mpsc::Receiver<HwBoundEvent>, tx: mpsc::Sender<AppBoundEvent>) {
+/// How long an identified device sweeps its needle
+const IDENTIFY_DURATION: Duration = Duration::from_millis(1500);
+
+/// A handshake blocks every device until it times out, so one that failed is retried less and less often
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Reads telemetry straight from the backend at its own rate, so devices keep updating without a window
+pub async fn main(mut config: watch::Receiver<HardwareConfig>, mut telemetry: TelemetryReceiver, mut rx: mpsc::Receiver<HwBoundEvent>, tx: mpsc::Sender<AppBoundEvent>) {
     let api = hidapi::HidApi::new().expect("Failed to construct HidApi!");
-    let mut device_list = Vec::new();
+    let mut devices = Devices::new(api, tx);
+    devices.scan();
+    let mut last_sequence = 0;
+    let mut rpm = 0;
+    let (mut update_interval, mut scan_interval) = {
+        let config = config.borrow_and_update();
+        (config.update_interval(), config.scan_interval())
+    };
+    let mut update = interval(update_interval);
+    let mut scan = interval(scan_interval);
+    let mut heartbeat = interval(Duration::from_millis(150));
 
     loop {
         tokio::select! {
-            maybe_event = rx.recv() => {
-                if let Some(event) = maybe_event {
-                    match event {
-                        HwBoundEvent::RequestDeviceList => {
-                            device_list = get_device_list(&api);
-                            // tx.send(AppBoundEvent::UpdateDeviceList(device_list_ids)).await;
-                        },
-                        HwBoundEvent::UpdateTelemetry(v) => {
-                            for device in &device_list {
-                                let _ = match device {
-                                    devices::Device::RpmGauge(rpm_gauge) => rpm_gauge.update_rpm(v.engine.rpm as u16),
-                                };
-                            }
-                        },
-                    }
+            Ok(()) = config.changed() => {
+                // A new interval ticks right away, so only the ones whose period changed are replaced
+                let config = config.borrow_and_update();
+                if config.update_interval() != update_interval {
+                    update_interval = config.update_interval();
+                    debug!("Updating devices every {update_interval:?}");
+                    update = interval(update_interval);
+                }
+                if config.scan_interval() != scan_interval {
+                    scan_interval = config.scan_interval();
+                    debug!("Looking for new devices every {scan_interval:?}");
+                    scan = interval(scan_interval);
                 }
             },
-            _ = tokio::time::sleep(tokio::time::Duration::from_millis(150)) => {
-                let mut new_device_list = Vec::new();
-                for device in device_list.drain(..) {
-                    let result = match &device {
-                        devices::Device::RpmGauge(rpm_gauge) => rpm_gauge.heartbeat(),
-                    };
-                    if let Err(e) = result {
-                        error!("Lost connection to device! Error: {:?}", e);
-                    } else {
-                        new_device_list.push(device);
+            _ = update.tick() => {
+                let fresh = telemetry.has_changed().unwrap_or(false);
+                if fresh {
+                    let frame = telemetry.borrow_and_update();
+                    if frame.sequence > last_sequence + 1 {
+                        trace!("Skipped {} telemetry frames", frame.sequence - last_sequence - 1);
                     }
+                    last_sequence = frame.sequence;
+                    rpm = frame.telemetry.engine.rpm as u16;
+                }
+                // A sweeping needle moves without new telemetry
+                if fresh || devices.is_identifying() {
+                    devices.update(rpm, &config.borrow());
+                }
+            },
+            // Without an app there is nobody to ask for anything, which disables this branch
+            Some(event) = rx.recv() => {
+                match event {
+                    HwBoundEvent::RequestDeviceList => {
+                        // Asked for by hand, so devices that didn't answer get another try right away
+                        devices.retry_now();
+                        devices.scan();
+                        devices.publish();
+                    },
+                    HwBoundEvent::Identify(id) => devices.identify(id),
+                    HwBoundEvent::ReadSettings(id) => devices.read_settings(id),
+                    HwBoundEvent::WriteSettings(id, settings) => devices.write_settings(id, settings),
+                    HwBoundEvent::Hold(id, raw) => devices.hold(id, raw),
                 }
-                device_list = new_device_list;
             },
+            _ = scan.tick() => devices.scan(),
+            _ = heartbeat.tick() => devices.heartbeat(),
         }
     }
 }
 
-fn get_device_list(api: &hidapi::HidApi) -> Vec<devices::Device> {
-    const SUPPORTED_VID: [u16; 1] = [
-        6991,
-    ];
+/// The connected devices, kept in sync with what is plugged in
+struct Devices {
+    api: hidapi::HidApi,
+    list: Vec<devices::Device>,
+    known: Vec<DeviceDescriptor>,   // Every device seen since startup, including the ones that are gone
+    identifying: Vec<(DeviceId, Instant)>,
+    held: Vec<(DeviceId, u16)>,
+    retries: Vec<(DeviceId, Instant, Duration)>, // Devices whose handshake failed, when to try next and how long was waited
+    tx: mpsc::Sender<AppBoundEvent>,
+}
 
-    const SUPPORTED_PID: [u16; 1] = [
-        37382,
-    ];
+impl Devices {
+    fn new(api: hidapi::HidApi, tx: mpsc::Sender<AppBoundEvent>) -> Self {
+        Self {
+            api,
+            list: Vec::new(),
+            known: Vec::new(),
+            identifying: Vec::new(),
+            held: Vec::new(),
+            retries: Vec::new(),
+            tx,
+        }
+    }
 
-    let mut device_list = Vec::new();
+    /// Attaches every supported device that was plugged in, or came back after a USB reset, since the last scan
+    fn scan(&mut self) {
+        if let Err(e) = self.api.refresh_devices() {
+            error!("Could not refresh the HID device list: {:?}", e);
+            return;
+        }
 
-    for hid_device in api.device_list() {
-        // println!("{:#?}", device);
-        // println!("{:?} ({:?}): {:?}", device.manufacturer_string(), device.product_string(), device);
+        let mut changed = false;
+        let mut seen: Vec<DeviceId> = Vec::new();
+        for info in supported_devices(&self.api) {
+            let mut id = DeviceId::of(info);
+            if seen.contains(&id) {
+                warn!("Another device has serial number {id}, telling them apart by port");
+                id = id.with_port(info);
+            }
+            seen.push(id.clone());
+            if self.list.iter().any(|device| device.id() == &id) {
+                continue;
+            }
+            if self.retries.iter().any(|(retry, at, _)| retry == &id && Instant::now() < *at) {
+                continue;
+            }
 
-        // Now we have to detect any device that is currently supported.
-        let vid = hid_device.vendor_id();
-        let pid = hid_device.product_id();
+            let index = match self.known.iter().position(|descriptor| descriptor.id == id) {
+                Some(index) => index,
+                None => {
+                    self.known.push(DeviceDescriptor::new(id.clone(), info));
+                    self.known.len() - 1
+                },
+            };
+            let descriptor = &mut self.known[index];
+            match devices::Device::from_hid_device(&self.api, info, id.clone()) {
+                Ok(device) => {
+                    info!("Device connected: {id}");
+                    // The firmware may have been updated while it was gone
+                    *descriptor = DeviceDescriptor { last_error: descriptor.last_error.take(), ..DeviceDescriptor::new(id.clone(), info) };
+                    device.describe(descriptor);
+                    descriptor.state = ConnectionState::Connected;
+                    self.list.push(device);
+                    self.retries.retain(|(retry, _, _)| retry != &id);
+                    self.notify(AppBoundEvent::DeviceConnected(id));
+                    changed = true;
+                },
+                Err(e) => {
+                    // Only reported once, it's retried with a growing delay
+                    if descriptor.state == ConnectionState::HandshakeFailed {
+                        trace!("Device {id} still doesn't answer: {:?}", e);
+                    } else {
+                        error!("Error trying to load device {id}: {:?}", e);
+                        descriptor.state = ConnectionState::HandshakeFailed;
+                        descriptor.last_error = Some(e.to_string());
+                        changed = true;
+                    }
+                    let delay = match self.retries.iter().position(|(retry, _, _)| retry == &id) {
+                        Some(index) => (self.retries.swap_remove(index).2 * 2).min(MAX_RETRY_DELAY),
+                        None => FIRST_RETRY_DELAY,
+                    };
+                    self.retries.push((id, Instant::now() + delay, delay));
+                },
+            }
+        }
+
+        // Unplugged devices get a fresh chance when they come back
+        self.retries.retain(|(id, _, _)| seen.contains(id));
+        for descriptor in &mut self.known {
+            if descriptor.state == ConnectionState::HandshakeFailed && !seen.contains(&descriptor.id) {
+                descriptor.state = ConnectionState::Disconnected;
+                changed = true;
+            }
+        }
+        if changed {
+            self.publish();
+        }
+    }
+
+    fn retry_now(&mut self) {
+        self.retries.clear();
+    }
 
-        if let hidapi::BusType::Usb = hid_device.bus_type() {
-            if SUPPORTED_VID.contains(&vid) && SUPPORTED_PID.contains(&pid) {
-                // We found a supported device
-                match devices::Device::from_hid_device(api, vid, pid) {
-                    Ok(device) => device_list.push(device),
-                    Err(e) => error!("Error trying to load device ({vid}:{pid}): {:?}", e),
+    /// Drops devices that stopped answering, the next scan reconnects them once they're back
+    fn heartbeat(&mut self) {
+        let mut lost = Vec::new();
+        self.list.retain(|device| {
+            let result = match device {
+                devices::Device::RpmGauge(rpm_gauge) => rpm_gauge.heartbeat(),
+            };
+            match result {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Lost connection to device {}! Error: {:?}", device.id(), e);
+                    lost.push((device.id().clone(), e.to_string()));
+                    false
+                },
+            }
+        });
+        if lost.is_empty() {
+            return;
+        }
+        for (id, error) in lost {
+            if let Some(descriptor) = self.known.iter_mut().find(|descriptor| descriptor.id == id) {
+                descriptor.state = ConnectionState::Disconnected;
+                descriptor.last_error = Some(error.clone());
+            }
+            self.notify(AppBoundEvent::DeviceDisconnected(id, error));
+        }
+        self.publish();
+    }
+
+    fn read_settings(&mut self, id: DeviceId) {
+        let result = match self.list.iter_mut().find(|device| device.id() == &id) {
+            Some(device) => device.configurable().read_settings().map_err(|e| e.to_string()),
+            None => Err("not connected".to_string()),
+        };
+        if let Err(e) = &result {
+            error!("Could not read settings of device {id}: {e}");
+        }
+        self.notify(AppBoundEvent::Settings(id, result));
+    }
+
+    fn write_settings(&mut self, id: DeviceId, settings: DeviceSettings) {
+        let result = match self.list.iter_mut().find(|device| device.id() == &id) {
+            Some(device) => device.configurable().write_settings(&settings).map_err(|e| e.to_string()).inspect(|()| {
+                // The descriptor shows the new max value and unit
+                if let Some(descriptor) = self.known.iter_mut().find(|descriptor| descriptor.id == id) {
+                    device.describe(descriptor);
                 }
+            }),
+            None => Err("not connected".to_string()),
+        };
+        match &result {
+            Ok(()) => info!("Wrote settings of device {id}"),
+            Err(e) => error!("Could not write settings of device {id}: {e}"),
+        }
+        let written = result.is_ok();
+        self.notify(AppBoundEvent::SettingsWritten(id, result));
+        if written {
+            self.publish();
+        }
+    }
+
+    fn identify(&mut self, id: DeviceId) {
+        self.identifying.retain(|(identifying, _)| identifying != &id);
+        self.identifying.push((id, Instant::now()));
+    }
+
+    fn hold(&mut self, id: DeviceId, raw: Option<u16>) {
+        self.held.retain(|(held, _)| held != &id);
+        let Some(raw) = raw else {
+            return;
+        };
+        if let Some(devices::Device::RpmGauge(rpm_gauge)) = self.list.iter().find(|device| device.id() == &id) {
+            if let Err(e) = rpm_gauge.update_rpm(raw) {
+                trace!("Could not update device {id}: {:?}", e);
             }
         }
+        self.held.push((id, raw));
     }
 
-    device_list
+    fn is_identifying(&self) -> bool {
+        !self.identifying.is_empty()
+    }
+
+    /// Shows the RPM through each device's calibration on every enabled device,
+    /// except the ones sweeping their needle to be found or held still for calibrating
+    fn update(&mut self, rpm: u16, config: &HardwareConfig) {
+        self.identifying.retain(|(_, start)| start.elapsed() < IDENTIFY_DURATION);
+        for device in &self.list {
+            if self.held.iter().any(|(id, _)| id == device.id()) {
+                continue;
+            }
+            let result = match self.identifying.iter().find(|(id, _)| id == device.id()) {
+                // Up to the end of the dial and back
+                Some((_, start)) => device.show_fraction(1.0 - (2.0 * start.elapsed().as_secs_f32() / IDENTIFY_DURATION.as_secs_f32() - 1.0).abs()),
+                None if !config.is_enabled(device.id()) => continue,
+                None => {
+                    let raw = config.device(device.id()).map_or(rpm as f32, |settings| settings.calibration.apply(rpm as f32));
+                    match device {
+                        devices::Device::RpmGauge(rpm_gauge) => rpm_gauge.update_rpm(raw.round().clamp(0.0, u16::MAX as f32) as u16),
+                    }
+                },
+            };
+            if let Err(e) = result {
+                trace!("Could not update device {}: {:?}", device.id(), e);
+            }
+        }
+    }
+
+    fn publish(&self) {
+        self.notify(AppBoundEvent::UpdateDeviceList(self.known.clone()));
+    }
+
+    /// Never waits for the app, which may be busy, closed or never started
+    fn notify(&self, event: AppBoundEvent) {
+        if let Err(e) = self.tx.try_send(event) {
+            trace!("App didn't take a device event: {e}");
+        }
+    }
+}
+
+const SUPPORTED_VID: [u16; 1] = [
+    6991,
+];
+
+const SUPPORTED_PID: [u16; 1] = [
+    37382,
+];
+
+/// Every connected HID device we know how to talk to, without opening any of them
+pub fn supported_devices(api: &hidapi::HidApi) -> Vec<&hidapi::DeviceInfo> {
+    api.device_list().filter(|info| {
+        matches!(info.bus_type(), hidapi::BusType::Usb) && SUPPORTED_VID.contains(&info.vendor_id()) && SUPPORTED_PID.contains(&info.product_id())
+    }).collect()
 }
@@ -1,28 +1,88 @@
 #[macro_use] extern crate log;
 
-use tokio::sync::mpsc;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use tokio::sync::{mpsc, watch};
+
+use telemetry::TelemetryFrame;
 
 mod telemetry;
+mod config;
 mod app;
 mod backend;
 mod hardware;
 
+#[derive(Parser)]
+#[command(version, about = "Sends sim racing telemetry to Dysoon gauges")]
+struct Cli {
+    /// Settings file to use, created by saving from the app
+    #[arg(long, global = true, default_value = config::DEFAULT_PATH)]
+    config: PathBuf,
+    /// Run without a window, logging to stdout, until interrupted
+    #[arg(long, global = true)]
+    headless: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Connect games to devices (the default)
+    Run,
+    /// List connected devices without opening them
+    ListDevices,
+    /// List supported games, where their telemetry is heard and which of them are running
+    ListGames,
+    /// Print live telemetry without driving any devices
+    Monitor {
+        /// Lines per second, at most
+        #[arg(long, default_value_t = 10.0)]
+        rate_hz: f32,
+    },
+}
+
 fn main() {
-    pretty_env_logger::formatted_timed_builder()
-        .filter_level(log::LevelFilter::Debug)
-        // .filter_level(log::LevelFilter::Info)
-        .init();
+    let cli = Cli::parse();
+    let headless = cli.headless || !matches!(cli.command, None | Some(Command::Run));
 
-    let (backend_tx, backend_rx) = mpsc::channel(100);
+    let mut logger = pretty_env_logger::formatted_timed_builder();
+    logger.filter_level(log::LevelFilter::Debug);
+    // logger.filter_level(log::LevelFilter::Info);
+    if headless {
+        logger.target(pretty_env_logger::env_logger::Target::Stdout);
+    }
+    logger.init();
+
+    let config = config::Config::load_or_default(&cli.config);
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run(config, cli.config, headless),
+        Command::ListDevices => list_devices(),
+        Command::ListGames => list_games(config),
+        Command::Monitor { rate_hz } => monitor(config, rate_hz),
+    }
+}
+
+fn run(config: config::Config, config_path: PathBuf, headless: bool) {
+    let (telemetry_tx, telemetry_rx) = watch::channel(TelemetryFrame::default());
+    let (games_config_tx, games_config_rx) = watch::channel(config.games.clone());
     let rt_backend = tokio::runtime::Runtime::new().expect("Failed to start backend runtime!");
-    let handle_backend = rt_backend.spawn(backend::main(backend_tx));
+    let handle_backend = rt_backend.spawn(backend::main(telemetry_tx, games_config_rx));
 
+    let (hardware_config_tx, hardware_config_rx) = watch::channel(config.hardware.clone());
     let (hardware_hwbound_tx, hardware_hwbound_rx) = mpsc::channel(100);
     let (hardware_appbound_tx, hardware_appbound_rx) = mpsc::channel(100);
     let rt_hardware = tokio::runtime::Runtime::new().expect("Failed to start hardware runtime!");
-    let handle_hardware = rt_hardware.spawn(hardware::main(hardware_hwbound_rx, hardware_appbound_tx));
+    let handle_hardware = rt_hardware.spawn(hardware::main(hardware_config_rx, telemetry_rx.clone(), hardware_hwbound_rx, hardware_appbound_tx));
 
-    app::main(backend_rx, hardware_hwbound_tx, hardware_appbound_rx);
+    if headless {
+        info!("Running headless, stop with Ctrl+C");
+        rt_backend.block_on(shutdown_signal());
+        info!("Shutting down");
+    } else {
+        app::main(telemetry_rx, hardware_hwbound_tx, hardware_appbound_rx, config, config_path, games_config_tx, hardware_config_tx);
+    }
 
     handle_backend.abort();
     rt_backend.shutdown_timeout(std::time::Duration::from_millis(10));
@@ -30,3 +90,95 @@ fn main() {
     handle_hardware.abort();
     rt_hardware.shutdown_timeout(std::time::Duration::from_millis(10));
 }
+
+fn list_devices() {
+    let api = match hidapi::HidApi::new() {
+        Ok(api) => api,
+        Err(e) => return error!("Could not list HID devices: {:?}", e),
+    };
+    let devices = hardware::supported_devices(&api);
+    if devices.is_empty() {
+        println!("No supported devices connected");
+    }
+    for info in devices {
+        println!(
+            "{}: {} ({:04x}:{:04x}) at {}",
+            hardware::DeviceId::of(info),
+            info.product_string().unwrap_or("unknown device"),
+            info.vendor_id(),
+            info.product_id(),
+            info.path().to_string_lossy(),
+        );
+    }
+}
+
+fn list_games(mut config: config::Config) {
+    println!("Telemetry is heard on:");
+    for (name, listen) in config.games.listeners_mut() {
+        println!("  {name}: {}:{}", listen.bind_address, listen.port);
+    }
+    println!("Games found by their executable:");
+    for status in backend::game_status(&config.games) {
+        let running = status.running.map(|rule| format!("running, found by {rule}")).unwrap_or_else(|| "not running".to_string());
+        println!("  {} ({}): {running}", status.game, status.executables.join(", "));
+    }
+}
+
+fn monitor(config: config::Config, rate_hz: f32) {
+    let (telemetry_tx, mut telemetry_rx) = watch::channel(TelemetryFrame::default());
+    let (_games_config_tx, games_config_rx) = watch::channel(config.games);
+    let rt_backend = tokio::runtime::Runtime::new().expect("Failed to start backend runtime!");
+    let handle_backend = rt_backend.spawn(backend::main(telemetry_tx, games_config_rx));
+
+    rt_backend.block_on(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs_f32(1.0 / rate_hz.clamp(0.1, 1000.0)));
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = interval.tick() => {
+                    if !telemetry_rx.has_changed().unwrap_or(false) {
+                        continue;
+                    }
+                    let frame = telemetry_rx.borrow_and_update().clone();
+                    let telemetry = &frame.telemetry;
+                    if telemetry.game.is_empty() {
+                        println!("#{} no game", frame.sequence);
+                    } else {
+                        println!(
+                            "#{} {}: gear {}, {} rpm, {:.0} km/h, throttle {:.0}%, brake {:.0}%",
+                            frame.sequence,
+                            telemetry.game,
+                            telemetry.general.gear,
+                            telemetry.engine.rpm,
+                            telemetry.general.speed * 3.6,
+                            telemetry.input.throttle * 100.0,
+                            telemetry.input.brake * 100.0,
+                        );
+                    }
+                },
+            }
+        }
+    });
+
+    handle_backend.abort();
+    rt_backend.shutdown_timeout(std::time::Duration::from_millis(10));
+}
+
+/// Resolves on Ctrl+C, or when the service manager asks us to stop
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM!");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+    #[cfg(not(unix))]
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Could not listen for Ctrl+C: {:?}", e);
+    }
+}
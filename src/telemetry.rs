@@ -1,3 +1,12 @@
+/// The newest telemetry, numbered so a reader can tell how many frames it missed since its last look
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryFrame {
+    pub sequence: u64,                  // Increases by one with every published frame, 0 before the first one
+    pub telemetry: Telemetry,
+}
+
+pub type TelemetryReceiver = tokio::sync::watch::Receiver<TelemetryFrame>;
+
 #[derive(Default, Debug, Clone)]
 pub struct Telemetry {
     pub game: &'static str,
@@ -5,19 +14,35 @@ pub struct Telemetry {
     pub general: TelemetryGeneral,
     pub engine: TelemetryEngine,
     pub input: TelemetryInput,
+    pub lights: TelemetryLights,
+    pub units: TelemetryUnits,
+    pub motion: TelemetryMotion,
+    pub wheels: Vec<TelemetryWheel>,    // Front left, front right, rear left, rear right. Empty if the game doesn't report wheels
+    pub timing: TelemetryTiming,
+    pub session: TelemetrySession,
+    pub drs: Option<TelemetryDrs>,      // None if the car has no DRS
+    pub ers: Option<TelemetryErs>,      // None if the car has no ERS
+    pub truck: Option<TelemetryTruck>,  // None if the game isn't a truck simulator
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct TelemetryGeneral {
     pub gear: isize,
     pub fuel: f32,  // Percentage, 0-1
+    pub fuel_capacity: Option<f32>, // Liters, None if the game doesn't report it
     pub speed: f32, // In meters per second
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct TelemetryEngine {
     pub rpm: usize,
-    pub turbo: Option<f32>, // In bar, None if there is no turbo present
+    pub max_rpm: Option<usize>,     // Redline, None if the game doesn't report it
+    pub idle_rpm: Option<usize>,    // None if the game doesn't report it
+    pub shift_rpm: Option<usize>,   // Where the in-game shift lights start, None if the game doesn't report it
+    pub turbo: Option<f32>,         // In bar, None if there is no turbo present
+    pub engine_temp: Option<f32>,   // Coolant temperature in celsius, None if the game doesn't report it
+    pub oil_temp: Option<f32>,      // In celsius, None if the game doesn't report it
+    pub oil_pressure: Option<f32>,  // In bar, None if the game doesn't report it
 }
 
 #[derive(Default, Debug, Clone)]
@@ -26,3 +51,168 @@ pub struct TelemetryInput {
     pub brake: f32,
     pub clutch: f32,
 }
+
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryLights {
+    pub available: DashLights,  // The dashboard lights that exist for this car
+    pub active: DashLights,     // Which of the available lights are currently on
+    pub rev_lights: Option<f32>, // Shift light progress as shown in-game, 0-1. None if the game doesn't report it
+}
+
+impl TelemetryLights {
+    pub fn is_on(&self, light: DashLights) -> bool {
+        self.available.contains(light) && self.active.contains(light)
+    }
+}
+
+bitflags::bitflags! {
+    /// Dashboard warning and indicator lamps.
+    /// The bit layout matches OutGauge's `DL_*` flags, so games speaking OutGauge can pass their bits through as-is.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct DashLights: u32 {
+        const SHIFT         = 1 << 0;
+        const FULL_BEAM     = 1 << 1;
+        const HANDBRAKE     = 1 << 2;
+        const PIT_SPEED     = 1 << 3;
+        const TC            = 1 << 4;
+        const SIGNAL_LEFT   = 1 << 5;
+        const SIGNAL_RIGHT  = 1 << 6;
+        const SIGNAL_ANY    = 1 << 7;
+        const OIL_WARNING   = 1 << 8;
+        const BATTERY       = 1 << 9;
+        const ABS           = 1 << 10;
+        const ENGINE        = 1 << 11;
+        const FOG_REAR      = 1 << 12;
+        const FOG_FRONT     = 1 << 13;
+        const DIPPED        = 1 << 14;
+        const FUEL_WARNING  = 1 << 15;
+        const SIDELIGHTS    = 1 << 16;
+        const NEUTRAL       = 1 << 17;
+    }
+}
+
+/// Units the player has selected in-game, so displays can follow them.
+/// Telemetry values themselves are always metric.
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryUnits {
+    pub prefers_miles: bool,
+    pub prefers_psi: bool,
+}
+
+/// Orientation and movement of the car in the world.
+/// World axes are X to the right (east), Y forward (north) and Z up; games using other conventions are converted.
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryMotion {
+    pub heading: f32,               // Radians, anticlockwise from above
+    pub pitch: f32,                 // Radians, nose up is positive
+    pub roll: f32,                  // Radians, anticlockwise seen from behind
+    pub angular_velocity: [f32; 3], // Radians per second around the world axes
+    pub acceleration: [f32; 3],     // M/S^2 along the world axes
+    pub velocity: [f32; 3],         // M/S along the world axes
+    pub position: [f64; 3],         // Meters
+}
+
+impl TelemetryMotion {
+    /// Acceleration relative to the car's heading (right, forward, up), ignoring pitch and roll.
+    /// Divide by 9.81 for G-forces.
+    pub fn local_acceleration(&self) -> [f32; 3] {
+        let (sin, cos) = self.heading.sin_cos();
+        let [x, y, z] = self.acceleration;
+        [x * cos + y * sin, y * cos - x * sin, z]
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryWheel {
+    pub suspension_travel: f32, // Meters of compression
+    pub angular_velocity: f32,  // Radians per second
+    pub speed: Option<f32>,     // Surface speed in M/S, None if the game doesn't report it
+    pub vertical_load: f32,     // Newton
+    pub slip_ratio: f32,
+    pub slip_angle: f32,        // Radians
+    pub tyre_temp: Option<f32>,     // Celsius, None if the game doesn't report it
+    pub tyre_pressure: Option<f32>, // Bar, None if the game doesn't report it
+    pub brake_temp: Option<f32>,    // Celsius, None if the game doesn't report it
+}
+
+/// Lap and race progress of the player. Times are in seconds.
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryTiming {
+    pub lap: Option<u32>,               // Current lap, starting at 1
+    pub position: Option<u32>,          // Race position, starting at 1
+    pub current_lap_time: Option<f32>,
+    pub last_lap_time: Option<f32>,
+    pub best_lap_time: Option<f32>,
+    pub delta_to_car_ahead: Option<f32>,
+    pub delta_to_leader: Option<f32>,
+    pub delta_to_best: Option<f32>,     // Live delta against the best lap, negative is faster
+    pub lap_distance: Option<f32>,      // Meters driven on the current lap
+    pub location: CarLocation,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarLocation {
+    #[default]
+    Unknown,
+    Track,
+    PitLane,
+    PitEntry,
+    PitExit,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TelemetrySession {
+    pub kind: SessionKind,
+    pub phase: SessionPhase,
+    pub total_laps: Option<u32>,
+    pub time_left: Option<f32>,     // Seconds
+    pub track_length: Option<f32>,  // Meters
+    pub air_temp: Option<f32>,      // Celsius
+    pub track_temp: Option<f32>,    // Celsius
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    #[default]
+    Unknown,
+    Practice,
+    Qualifying,
+    Race,
+    TimeTrial,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionPhase {
+    #[default]
+    Unknown,
+    PreSession, // Waiting for the session to start, including formation laps
+    Running,
+    Finished,   // The session is over, but cars may still be completing their final lap
+    PostSession,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryDrs {
+    pub allowed: bool,
+    pub active: bool,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryErs {
+    pub store: f32,             // Percentage, 0-1
+    pub deploy_mode: u8,        // Game specific
+    pub deployed_this_lap: f32, // Percentage of the per-lap allowance, 0-1
+}
+
+/// Systems only trucks have.
+#[derive(Default, Debug, Clone)]
+pub struct TelemetryTruck {
+    pub air_pressure: f32,              // Brake air pressure in bar
+    pub air_pressure_warning: bool,
+    pub cruise_control: Option<f32>,    // Set speed in M/S, None if cruise control is off
+    pub retarder_step: u32,             // 0 when off
+    pub retarder_steps: u32,            // 0 if the truck has no retarder
+    pub engine_brake: bool,
+    pub navigation_distance: Option<f32>, // Meters to the destination, None without a route
+    pub speed_limit: Option<f32>,       // M/S, None if there is none
+}